@@ -1,29 +1,61 @@
+use std::collections::{BTreeSet, HashSet};
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Order, Reply,
+    Response, StdResult, Storage, SubMsg, SubMsgResult, WasmMsg,
 };
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
 
 use ratesync::{
-    lsr_helpers::validate_redemption_rate,
+    lsr_helpers::{calculate_twap, detect_anomaly},
     lsr_msg::{
-        ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, RedemptionRateResponse,
-        RedemptionRates,
+        oracle::{Confidence, PriceInfoResponse, PriceResponse},
+        AllRedemptionRatesResponse, AnomalyConfigResponse, ConfigResponse, DenomInfoResponse,
+        DenomRedemptionRate, ExecuteMsg, HistoryCapacityResponse, IbcConnectionsResponse,
+        InstantiateMsg, InterpolationMode, LiquidStakeRateEntry, LiquidStakeRateResult,
+        LiquidStakeRatesResponse, MaxAgeResponse, MigrateMsg, PauseStatusResponse, QueryMsg,
+        QuorumConfigResponse, RateHookMsg, RateOrder, RedemptionRateAtResponse,
+        RedemptionRateParams, RedemptionRateResponse, RedemptionRates, RoleMembersResponse,
+        StalenessMode, StkDenomsResponse, SubscriberInfo, SubscribersResponse, SudoMsg,
+        TransferPathResponse, TransferPathsResponse,
     },
     lsr_state::{
-        AnomalyConfig, Config, History, RedemptionRate, ANOMALY_CONFIG_BY_DENOM, CONFIG,
-        LIQUID_STAKE_RATES,
+        AnomalyConfig, AnomalyPolicy, Config, DenomMetadata, DenomTombstone, DenomTrace,
+        HookFailure, PendingHook, RedemptionRate, Role, Subscription, TransferPath,
+        ALLOWED_IBC_CONNECTIONS, ANOMALY_CONFIG_BY_DENOM, CONFIG, DEFAULT_ANOMALY_CONFIG,
+        DENOM_METADATA, GLOBAL_PAUSE, HISTORY_HEADS, HISTORY_ITEM_CAP, MAX_AGE_BY_DENOM,
+        PAUSE_BY_DENOM, PENDING_HOOKS, REDEMPTION_RATES, RETIRED_DENOMS, ROLE_MEMBERS,
+        STK_DENOMS_BY_BOND_DENOM, SUBSCRIPTIONS, TRANSFER_PATH_BY_DENOM,
     },
 };
 
 use ratesync::{
     lsr_error::ContractError,
     lsr_helpers::{
-        denom_trace_to_hash, option_string_to_addr, validate_channel_id, validate_native_denom,
+        ensure_admin, ensure_not_retired, ensure_role, get_default_anomaly_config,
+        get_history_capacity, get_pause_flags, get_transfer_path, is_stale, load_history_head,
+        option_string_to_addr, parse_redemption_rate_params, stk_denom_to_hash, update_pause_flags,
+        validate_channel_id, validate_count_limit, validate_native_denom,
+    },
+    lsr_history::{
+        add_rate, clear_history, find_rate, find_surrounding_rates, range_rates, remove_rate,
+        set_history_capacity,
+    },
+    lsr_round::{
+        clear_rounds, prune_rounds, QuorumConfig, Round, Submission, QUORUM_CONFIG, ROUNDS,
+    },
+    migrations::migrate_contract,
+    ownership::{
+        accept_ownership, cancel_ownership, propose_ownership, query_pending_ownership,
+        PENDING_OWNERSHIP,
     },
 };
 
+use crate::migrations::MIGRATIONS;
+
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:liquid-stake-rate";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -31,6 +63,16 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const DEFAULT_DEVIAITON_COUNT_LIMIT: u64 = 10;
 const DEFAULT_DEVIAITON_THRESHOLD: Decimal = Decimal::percent(5);
 
+// Pagination of the AllRedemptionRates query
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// Gas given to each subscriber hook, so a subscriber running out of gas only fails its hook
+const HOOK_GAS_LIMIT: u64 = 500_000;
+
+// Rounds that don't reach the quorum within a day of their first submission are dropped
+const ROUND_EXPIRY_SECONDS: u64 = 24 * 60 * 60;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -49,15 +91,30 @@ pub fn instantiate(
         .deviation_threshold
         .unwrap_or(DEFAULT_DEVIAITON_THRESHOLD);
 
+    validate_count_limit(count_limit, HISTORY_ITEM_CAP)?;
+
+    DEFAULT_ANOMALY_CONFIG.save(
+        deps.storage,
+        &AnomalyConfig {
+            count_limit,
+            threshold,
+            ..AnomalyConfig::default()
+        },
+    )?;
+
+    let owner = option_string_to_addr(deps.api, msg.admin, info.sender.clone())?;
     CONFIG.save(
         deps.storage,
         &Config {
-            owner: option_string_to_addr(deps.api, msg.admin, info.sender.clone())?,
+            owner: owner.clone(),
             transfer_channel_i_d: msg.transfer_channel_i_d.clone(),
             transfer_port_i_d: msg.transfer_port_i_d.clone(),
         },
     )?;
 
+    // The admin posts rates until dedicated feeders are granted the role
+    ROLE_MEMBERS.save(deps.storage, (Role::Feeder.as_str(), &owner), &Empty {})?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender)
@@ -80,6 +137,7 @@ pub fn execute(
             stk_denom,
             c_value,
             controller_chain_time,
+            denom_trace,
         } => execute_add_liquid_stake_rate(
             deps,
             env,
@@ -88,8 +146,13 @@ pub fn execute(
             stk_denom,
             c_value,
             controller_chain_time,
+            denom_trace,
         ),
 
+        ExecuteMsg::LiquidStakeRates { rates } => {
+            execute_add_liquid_stake_rates(deps, env, info, rates)
+        }
+
         ExecuteMsg::UpdateConfig {
             transfer_channel_i_d,
             transfer_port_i_d,
@@ -99,50 +162,642 @@ pub fn execute(
             stk_denom,
             deviation_count_limit,
             deviation_threshold,
-        } => execute_set_anomaly_config(
+            threshold_mode,
+            policy,
+            strategy,
+            exclude_flagged,
+        } => {
+            let current = current_anomaly_config(deps.as_ref(), Some(&stk_denom))?;
+            execute_set_anomaly_config(
+                deps,
+                env,
+                info,
+                stk_denom,
+                AnomalyConfig {
+                    count_limit: deviation_count_limit,
+                    threshold: deviation_threshold,
+                    threshold_mode: threshold_mode.unwrap_or(current.threshold_mode),
+                    policy: policy.unwrap_or(current.policy),
+                    strategy: strategy.unwrap_or(current.strategy),
+                    exclude_flagged: exclude_flagged.unwrap_or(current.exclude_flagged),
+                },
+            )
+        }
+
+        ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit,
+            deviation_threshold,
+            threshold_mode,
+            policy,
+            strategy,
+            exclude_flagged,
+        } => {
+            let current = current_anomaly_config(deps.as_ref(), None)?;
+            execute_set_default_anomaly_config(
+                deps,
+                env,
+                info,
+                AnomalyConfig {
+                    count_limit: deviation_count_limit,
+                    threshold: deviation_threshold,
+                    threshold_mode: threshold_mode.unwrap_or(current.threshold_mode),
+                    policy: policy.unwrap_or(current.policy),
+                    strategy: strategy.unwrap_or(current.strategy),
+                    exclude_flagged: exclude_flagged.unwrap_or(current.exclude_flagged),
+                },
+            )
+        }
+
+        ExecuteMsg::SetTransferPath {
+            stk_denom,
+            transfer_port_i_d,
+            transfer_channel_i_d,
+            intermediate_hops,
+        } => execute_set_transfer_path(
             deps,
             env,
             info,
             stk_denom,
-            deviation_count_limit,
-            deviation_threshold,
+            TransferPath {
+                transfer_port_i_d,
+                transfer_channel_i_d,
+                intermediate_hops: intermediate_hops.unwrap_or_default(),
+            },
         ),
+
+        ExecuteMsg::RemoveTransferPath { stk_denom } => {
+            execute_remove_transfer_path(deps, env, info, stk_denom)
+        }
+
+        ExecuteMsg::SetHistoryCapacity {
+            stk_denom,
+            capacity,
+        } => execute_set_history_capacity(deps, env, info, stk_denom, capacity),
+
+        ExecuteMsg::SetMaxAge {
+            stk_denom,
+            max_age_seconds,
+        } => execute_set_max_age(deps, env, info, stk_denom, max_age_seconds),
+
+        ExecuteMsg::ProposeOwnership {
+            new_owner,
+            expires_at,
+        } => execute_propose_ownership(deps, env, info, new_owner, expires_at),
+
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+
+        ExecuteMsg::CancelOwnership {} => execute_cancel_ownership(deps, env, info),
+
+        ExecuteMsg::GrantRole { role, address } => {
+            execute_grant_role(deps, env, info, role, address)
+        }
+
+        ExecuteMsg::RevokeRole { role, address } => {
+            execute_revoke_role(deps, env, info, role, address)
+        }
+
+        ExecuteMsg::QuarantineRate { denom, update_time } => {
+            execute_quarantine_rate(deps, env, info, denom, update_time)
+        }
+
+        ExecuteMsg::AllowIbcConnection { connection_id } => {
+            execute_allow_ibc_connection(deps, env, info, connection_id)
+        }
+        ExecuteMsg::DisallowIbcConnection { connection_id } => {
+            execute_disallow_ibc_connection(deps, env, info, connection_id)
+        }
+        ExecuteMsg::SetPause {
+            denom,
+            ingestion,
+            serving,
+        } => execute_set_pause(deps, env, info, denom, ingestion, serving),
+        ExecuteMsg::ResolveQuarantinedRate {
+            denom,
+            update_time,
+            approve,
+        } => execute_resolve_quarantined_rate(deps, env, info, denom, update_time, approve),
+        ExecuteMsg::RemoveDenom { denom, tombstone } => {
+            execute_remove_denom(deps, env, info, denom, tombstone.unwrap_or(false))
+        }
+        ExecuteMsg::AddSubscriber { denom, address } => {
+            execute_add_subscriber(deps, env, info, denom, address)
+        }
+        ExecuteMsg::RemoveSubscriber { denom, address } => {
+            execute_remove_subscriber(deps, env, info, denom, address)
+        }
+        ExecuteMsg::SetQuorumConfig { quorum_config } => {
+            execute_set_quorum_config(deps, env, info, quorum_config)
+        }
     }
 }
 
 // Set liquid stake rate
+#[allow(clippy::too_many_arguments)]
 pub fn execute_add_liquid_stake_rate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     default_bond_denom: String,
     stk_denom: String,
     c_value: Decimal,
     controller_chain_time: u64,
+    denom_trace: Option<DenomTrace>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_role(deps.storage, Role::Feeder, &info.sender)?;
 
-    // Validate denom
-    validate_native_denom(&default_bond_denom.clone())?;
+    if let Some(quorum_config) = QUORUM_CONFIG.may_load(deps.storage)? {
+        let entry = LiquidStakeRateEntry {
+            default_bond_denom,
+            stk_denom,
+            c_value,
+            controller_chain_time,
+            denom_trace,
+        };
+        return execute_submit_liquid_stake_rate(deps, env, &config, &quorum_config, info, entry);
+    }
 
-    // Convert stk_denom to ibc hash
-    let stk_denom_ibc_hash = denom_trace_to_hash(
+    let prepared = prepare_liquid_stake_rate(
+        deps.as_ref(),
+        &config,
+        &default_bond_denom,
         &stk_denom,
-        &config.transfer_port_i_d,
-        &config.transfer_channel_i_d,
+        c_value,
+        controller_chain_time,
+        denom_trace,
     )?;
+    save_prepared_rate(deps.storage, &prepared)?;
+    let hooks = rate_hooks(deps.storage, &[&prepared.rate])?;
+    let new_liquid_stake_rate = prepared.rate;
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "set_liquid_stake_rate")
+        .add_attribute("default_bond_denom", default_bond_denom)
+        .add_attribute("stk_denom", stk_denom)
+        .add_attribute("stk_denom_ibc_hash", new_liquid_stake_rate.denom)
+        .add_attribute("c_value", c_value.to_string())
+        .add_attribute("controller_chain_time", controller_chain_time.to_string())
+        .add_attribute(
+            "anomaly_detected",
+            new_liquid_stake_rate.anomaly_detected.to_string(),
+        )
+        .add_attribute("quarantined", new_liquid_stake_rate.quarantined.to_string()))
+}
+
+// Submit a liquid stake rate to its round, committing the median once the quorum agrees
+fn execute_submit_liquid_stake_rate(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    quorum_config: &QuorumConfig,
+    info: MessageInfo,
+    entry: LiquidStakeRateEntry,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new()
+        .add_attribute("action", "submit_liquid_stake_rate")
+        .add_attribute("default_bond_denom", &entry.default_bond_denom)
+        .add_attribute("stk_denom", &entry.stk_denom)
+        .add_attribute("c_value", entry.c_value.to_string())
+        .add_attribute(
+            "controller_chain_time",
+            entry.controller_chain_time.to_string(),
+        );
+
+    let prepared = prepare_submission(
+        deps.as_ref(),
+        &env,
+        config,
+        quorum_config,
+        &info.sender,
+        entry,
+    )?;
+    let submitted = save_submission(deps.storage, &env, prepared)?;
+    response = response
+        .add_attribute("stk_denom_ibc_hash", &submitted.denom)
+        .add_attribute("submissions", submitted.round.submissions.len().to_string())
+        .add_attribute("committed", submitted.committed.is_some().to_string())
+        .add_attribute(
+            "round_status",
+            submitted.round.status(&info.sender).to_string(),
+        );
+
+    if let Some(rate) = submitted.committed {
+        let dissenters: Vec<&str> = submitted
+            .round
+            .dissenters
+            .iter()
+            .map(Addr::as_str)
+            .collect();
+        let hooks = rate_hooks(deps.storage, &[&rate])?;
+        response = response
+            .add_submessages(hooks)
+            .add_attribute("committed_rate", rate.redemption_rate.to_string())
+            .add_attribute("anomaly_detected", rate.anomaly_detected.to_string())
+            .add_attribute("quarantined", rate.quarantined.to_string())
+            .add_attribute("dissenters", dissenters.join(","));
+    }
+
+    Ok(response)
+}
+
+/// A submission validated against the round of its denom and time, along with the rate
+/// it commits
+struct PreparedSubmission {
+    denom: String,
+    update_time: u64,
+    round: Round,
+    /// Rate to save to the history if the submission reached the quorum
+    committed: Option<PreparedRate>,
+}
+
+/// A rate submitted to the round of its denom and time
+struct SubmittedRate {
+    denom: String,
+    round: Round,
+    /// Rate saved to the history if the submission reached the quorum
+    committed: Option<RedemptionRate>,
+}
+
+/// Records the rate of a feeder in the round of its denom and time without saving it. The
+/// median of the round is validated like a posted rate once a quorum of submissions agrees
+/// with it, and the round is marked rejected if the anomaly policy of the denom refuses the
+/// median.
+fn prepare_submission(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    quorum_config: &QuorumConfig,
+    feeder: &Addr,
+    entry: LiquidStakeRateEntry,
+) -> Result<PreparedSubmission, ContractError> {
+    let denom = resolve_stk_denom(
+        deps,
+        config,
+        &entry.default_bond_denom,
+        &entry.stk_denom,
+        entry.denom_trace.clone(),
+    )?;
+    let update_time = entry.controller_chain_time;
+    let now = env.block.time.seconds();
+
+    // A round that is pruned when the submission is saved starts over
+    let oldest_update_time = oldest_update_time(deps.storage, &denom)?;
+    let mut round = ROUNDS
+        .may_load(deps.storage, (&denom, update_time))?
+        .filter(|round| {
+            update_time >= oldest_update_time
+                && !round.is_expired(now.saturating_sub(ROUND_EXPIRY_SECONDS))
+        })
+        .unwrap_or_else(|| Round {
+            opened_at: now,
+            ..Round::default()
+        });
+    if round.has_submitted(feeder) {
+        return Err(ContractError::DuplicateSubmission {
+            denom,
+            update_time,
+            feeder: feeder.to_string(),
+        });
+    }
+
+    let submission = Submission {
+        feeder: feeder.clone(),
+        redemption_rate: entry.c_value,
+        metadata: DenomMetadata {
+            stk_denom: entry.stk_denom.clone(),
+            default_bond_denom: entry.default_bond_denom.clone(),
+        },
+    };
+    let committed = match round.submit(quorum_config, submission) {
+        Some(median) => {
+            let prepared = prepare_liquid_stake_rate(
+                deps,
+                config,
+                &entry.default_bond_denom,
+                &entry.stk_denom,
+                median,
+                update_time,
+                entry.denom_trace,
+            );
+            match prepared {
+                Ok(prepared) => Some(prepared),
+                // The round is kept so that later submissions don't fail on it
+                Err(ContractError::AnomalousRedemptionRate { .. }) => {
+                    round.reject();
+                    None
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        None => None,
+    };
+
+    Ok(PreparedSubmission {
+        denom,
+        update_time,
+        round,
+        committed,
+    })
+}
+
+/// Saves the round of a prepared submission along with the rate it commits
+fn save_submission(
+    storage: &mut dyn Storage,
+    env: &Env,
+    prepared: PreparedSubmission,
+) -> StdResult<SubmittedRate> {
+    let PreparedSubmission {
+        denom,
+        update_time,
+        round,
+        committed,
+    } = prepared;
+
+    // Rounds older than the history can no longer commit a rate that is kept, and rounds
+    // that didn't reach the quorum in time are not expected to
+    let oldest_update_time = oldest_update_time(storage, &denom)?;
+    prune_rounds(
+        storage,
+        &denom,
+        oldest_update_time,
+        env.block
+            .time
+            .seconds()
+            .saturating_sub(ROUND_EXPIRY_SECONDS),
+    )?;
+    ROUNDS.save(storage, (&denom, update_time), &round)?;
+
+    if let Some(prepared) = &committed {
+        save_prepared_rate(storage, prepared)?;
+    }
+
+    Ok(SubmittedRate {
+        denom,
+        round,
+        committed: committed.map(|prepared| prepared.rate),
+    })
+}
+
+// Update time of the oldest rate kept in the history of a denom
+fn oldest_update_time(storage: &dyn Storage, denom: &str) -> StdResult<u64> {
+    Ok(find_rate(storage, denom, Order::Ascending, |_| true)?.map_or(0, |rr| rr.update_time))
+}
+
+// Set liquid stake rates of several denoms at once, failing the whole batch if any entry fails
+pub fn execute_add_liquid_stake_rates(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rates: Vec<LiquidStakeRateEntry>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    // check if anomaly config exists, else set default
+    ensure_role(deps.storage, Role::Feeder, &info.sender)?;
+
+    let (results, hooks) = match QUORUM_CONFIG.may_load(deps.storage)? {
+        Some(quorum_config) => {
+            submit_liquid_stake_rates(deps, &env, &config, &quorum_config, &info.sender, rates)?
+        }
+        None => ingest_liquid_stake_rates(deps, &config, rates)?,
+    };
+    let anomalies = results.iter().filter(|r| r.anomaly_detected).count();
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_attribute("action", "set_liquid_stake_rates")
+        .add_attribute("count", results.len().to_string())
+        .add_attribute("anomalies_detected", anomalies.to_string())
+        .set_data(to_json_binary(&LiquidStakeRatesResponse { results })?))
+}
+
+/// Submits every rate of a batch to its round, returning the round status of every rate.
+/// Every submission is validated before any round is saved, so the batch is stored as a
+/// whole or not at all.
+pub fn submit_liquid_stake_rates(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    quorum_config: &QuorumConfig,
+    feeder: &Addr,
+    rates: Vec<LiquidStakeRateEntry>,
+) -> Result<(Vec<LiquidStakeRateResult>, Vec<SubMsg>), ContractError> {
+    if rates.is_empty() {
+        return Err(ContractError::EmptyRateBatch {});
+    }
+
+    let mut seen_denoms = HashSet::new();
+    let mut prepared_submissions = Vec::with_capacity(rates.len());
+    for entry in rates {
+        let stk_denom = entry.stk_denom.clone();
+        let prepared =
+            prepare_submission(deps.as_ref(), env, config, quorum_config, feeder, entry)?;
+
+        if !seen_denoms.insert(prepared.denom.clone()) {
+            return Err(ContractError::DuplicateRateBatchEntry {
+                denom: prepared.denom,
+            });
+        }
+
+        prepared_submissions.push((stk_denom, prepared));
+    }
+
+    let mut committed_rates = vec![];
+    let mut results = Vec::with_capacity(prepared_submissions.len());
+    for (stk_denom, prepared) in prepared_submissions {
+        let submitted = save_submission(deps.storage, env, prepared)?;
+
+        results.push(LiquidStakeRateResult {
+            stk_denom,
+            stk_denom_ibc_hash: submitted.denom,
+            anomaly_detected: submitted
+                .committed
+                .as_ref()
+                .is_some_and(|rate| rate.anomaly_detected),
+            quarantined: submitted
+                .committed
+                .as_ref()
+                .is_some_and(|rate| rate.quarantined),
+            round_status: Some(submitted.round.status(feeder)),
+        });
+        committed_rates.extend(submitted.committed);
+    }
+
+    // Hooks are built once so that reply IDs index a single list of pending hooks
+    let committed_rates: Vec<&RedemptionRate> = committed_rates.iter().collect();
+    let hooks = rate_hooks(deps.storage, &committed_rates)?;
+
+    Ok((results, hooks))
+}
+
+/// Validates every rate of a batch before storing any of them, so the batch is stored
+/// as a whole or not at all. Returns the hooks to send to the subscribers of the rates.
+pub fn ingest_liquid_stake_rates(
+    deps: DepsMut,
+    config: &Config,
+    rates: Vec<LiquidStakeRateEntry>,
+) -> Result<(Vec<LiquidStakeRateResult>, Vec<SubMsg>), ContractError> {
+    if rates.is_empty() {
+        return Err(ContractError::EmptyRateBatch {});
+    }
+
+    let mut seen_denoms = HashSet::new();
+    let mut prepared_rates = Vec::with_capacity(rates.len());
+    for entry in rates {
+        let prepared = prepare_liquid_stake_rate(
+            deps.as_ref(),
+            config,
+            &entry.default_bond_denom,
+            &entry.stk_denom,
+            entry.c_value,
+            entry.controller_chain_time,
+            entry.denom_trace,
+        )?;
+
+        if !seen_denoms.insert(prepared.rate.denom.clone()) {
+            return Err(ContractError::DuplicateRateBatchEntry {
+                denom: prepared.rate.denom,
+            });
+        }
+
+        prepared_rates.push((entry.stk_denom, prepared));
+    }
+
+    let mut results = Vec::with_capacity(prepared_rates.len());
+    for (stk_denom, prepared) in &prepared_rates {
+        save_prepared_rate(deps.storage, prepared)?;
+
+        results.push(LiquidStakeRateResult {
+            stk_denom: stk_denom.clone(),
+            stk_denom_ibc_hash: prepared.rate.denom.clone(),
+            anomaly_detected: prepared.rate.anomaly_detected,
+            quarantined: prepared.rate.quarantined,
+            round_status: None,
+        });
+    }
+
+    let saved_rates: Vec<&RedemptionRate> = prepared_rates.iter().map(|(_, p)| &p.rate).collect();
+    let hooks = rate_hooks(deps.storage, &saved_rates)?;
+
+    Ok((results, hooks))
+}
+
+// Builds a RateUpdated hook for each subscriber of the served rates, remembering which
+// subscriber each reply ID stands for
+fn rate_hooks(storage: &mut dyn Storage, rates: &[&RedemptionRate]) -> StdResult<Vec<SubMsg>> {
+    let mut pending_hooks = vec![];
+    let mut hooks = vec![];
+
+    for rate in rates.iter().filter(|rr| !rr.quarantined) {
+        let subscribers = SUBSCRIPTIONS
+            .prefix(&rate.denom)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for subscriber in subscribers {
+            let msg = WasmMsg::Execute {
+                contract_addr: subscriber.to_string(),
+                msg: to_json_binary(&RateHookMsg::RateUpdated {
+                    denom: rate.denom.clone(),
+                    redemption_rate: rate.redemption_rate,
+                    update_time: rate.update_time,
+                })?,
+                funds: vec![],
+            };
+            hooks.push(
+                SubMsg::reply_on_error(msg, pending_hooks.len() as u64)
+                    .with_gas_limit(HOOK_GAS_LIMIT),
+            );
+            pending_hooks.push(PendingHook {
+                denom: rate.denom.clone(),
+                subscriber,
+                update_time: rate.update_time,
+            });
+        }
+    }
+
+    if !pending_hooks.is_empty() {
+        PENDING_HOOKS.save(storage, &pending_hooks)?;
+    }
+
+    Ok(hooks)
+}
+
+/// A validated rate along with the denom state to save for it
+struct PreparedRate {
+    metadata: DenomMetadata,
+    anomaly_config: AnomalyConfig,
+    rate: RedemptionRate,
+}
+
+// Resolves the ibc hash of a posted stkToken, checking that it takes new rates
+fn resolve_stk_denom(
+    deps: Deps,
+    config: &Config,
+    default_bond_denom: &str,
+    stk_denom: &str,
+    denom_trace: Option<DenomTrace>,
+) -> Result<String, ContractError> {
+    // Validate denom
+    validate_native_denom(default_bond_denom)?;
+
+    // Convert stk_denom to ibc hash, preferring the denom trace if one was provided
+    let stk_denom_ibc_hash = match denom_trace {
+        Some(trace) => {
+            if trace.base_denom != stk_denom {
+                return Err(ContractError::InvalidDenomTrace {
+                    reason: format!(
+                        "base denom {} does not match stk_denom {stk_denom}",
+                        trace.base_denom
+                    ),
+                });
+            }
+            trace.validate()?;
+            trace.ibc_hash()?
+        }
+        None => stk_denom_to_hash(deps.storage, config, stk_denom)?,
+    };
+
+    ensure_not_retired(deps.storage, &stk_denom_ibc_hash)?;
+
+    if get_pause_flags(deps.storage, &stk_denom_ibc_hash)?.ingestion {
+        return Err(ContractError::Paused {
+            denom: stk_denom_ibc_hash,
+            operation: "ingestion".to_string(),
+        });
+    }
+
+    Ok(stk_denom_ibc_hash)
+}
+
+// Validates a liquid stake rate against the denom's anomaly config without saving it
+fn prepare_liquid_stake_rate(
+    deps: Deps,
+    config: &Config,
+    default_bond_denom: &str,
+    stk_denom: &str,
+    c_value: Decimal,
+    controller_chain_time: u64,
+    denom_trace: Option<DenomTrace>,
+) -> Result<PreparedRate, ContractError> {
+    let stk_denom_ibc_hash =
+        resolve_stk_denom(deps, config, default_bond_denom, stk_denom, denom_trace)?;
+
+    // check if anomaly config exists, else set default that fits in the history
     let anomaly_config =
         match ANOMALY_CONFIG_BY_DENOM.may_load(deps.storage, &stk_denom_ibc_hash)? {
             Some(config) => config,
-            None => AnomalyConfig::default(),
+            None => {
+                let default_config = get_default_anomaly_config(deps.storage)?;
+                AnomalyConfig {
+                    count_limit: default_config
+                        .count_limit
+                        .min(get_history_capacity(deps.storage, &stk_denom_ibc_hash)?),
+                    ..default_config
+                }
+            }
         };
-    ANOMALY_CONFIG_BY_DENOM.save(deps.storage, &stk_denom_ibc_hash.clone(), &anomaly_config)?;
 
     // Add liquid stake rate to historical state
     let mut new_liquid_stake_rate = RedemptionRate {
@@ -150,36 +805,57 @@ pub fn execute_add_liquid_stake_rate(
         redemption_rate: c_value,
         update_time: controller_chain_time,
         anomaly_detected: false,
+        quarantined: false,
+        overridden: false,
     };
 
-    let mut liquid_stake_rate_history =
-        match LIQUID_STAKE_RATES.may_load(deps.storage, &stk_denom_ibc_hash.clone())? {
-            Some(history) => {
-                new_liquid_stake_rate.anomaly_detected =
-                    validate_redemption_rate(deps.as_ref(), c_value, stk_denom_ibc_hash.clone())?;
+    if HISTORY_HEADS.has(deps.storage, &stk_denom_ibc_hash) {
+        new_liquid_stake_rate.anomaly_detected =
+            detect_anomaly(deps.storage, &anomaly_config, &stk_denom_ibc_hash, c_value)?;
+    }
 
-                history
+    // Apply the denom's anomaly policy
+    if new_liquid_stake_rate.anomaly_detected {
+        match anomaly_config.policy {
+            AnomalyPolicy::AcceptAndFlag => {}
+            AnomalyPolicy::Quarantine => new_liquid_stake_rate.quarantined = true,
+            AnomalyPolicy::Reject => {
+                return Err(ContractError::AnomalousRedemptionRate {
+                    denom: stk_denom_ibc_hash,
+                    value: c_value,
+                })
             }
-            None => History::<RedemptionRate>::default(),
-        };
-    liquid_stake_rate_history.add(new_liquid_stake_rate.clone());
-    LIQUID_STAKE_RATES.save(
-        deps.storage,
-        &stk_denom_ibc_hash.clone(),
-        &liquid_stake_rate_history,
-    )?;
+        }
+    }
 
-    Ok(Response::new()
-        .add_attribute("action", "set_liquid_stake_rate")
-        .add_attribute("default_bond_denom", default_bond_denom)
-        .add_attribute("stk_denom", stk_denom)
-        .add_attribute("stk_denom_ibc_hash", stk_denom_ibc_hash)
-        .add_attribute("c_value", c_value.to_string())
-        .add_attribute("controller_chain_time", controller_chain_time.to_string())
-        .add_attribute(
-            "anomaly_detected",
-            new_liquid_stake_rate.anomaly_detected.to_string(),
-        ))
+    Ok(PreparedRate {
+        metadata: DenomMetadata {
+            stk_denom: stk_denom.to_string(),
+            default_bond_denom: default_bond_denom.to_string(),
+        },
+        anomaly_config,
+        rate: new_liquid_stake_rate,
+    })
+}
+
+fn save_prepared_rate(storage: &mut dyn Storage, prepared: &PreparedRate) -> StdResult<()> {
+    let denom = prepared.rate.denom.as_str();
+    save_denom_metadata(storage, denom, &prepared.metadata)?;
+    ANOMALY_CONFIG_BY_DENOM.save(storage, denom, &prepared.anomaly_config)?;
+    add_rate(storage, &prepared.rate)
+}
+
+// Stores the denoms of an stkToken and moves it to the index of its bond denom
+fn save_denom_metadata(
+    storage: &mut dyn Storage,
+    denom: &str,
+    metadata: &DenomMetadata,
+) -> StdResult<()> {
+    if let Some(previous) = DENOM_METADATA.may_load(storage, denom)? {
+        STK_DENOMS_BY_BOND_DENOM.remove(storage, (&previous.default_bond_denom, denom));
+    }
+    STK_DENOMS_BY_BOND_DENOM.save(storage, (&metadata.default_bond_denom, denom), &Empty {})?;
+    DENOM_METADATA.save(storage, denom, metadata)
 }
 
 // Update config
@@ -192,9 +868,7 @@ pub fn execute_update_config(
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_admin(&config, &info.sender)?;
 
     if let Some(channel_id) = transfer_channel_i_d.clone() {
         validate_channel_id(&channel_id)?;
@@ -222,529 +896,4973 @@ pub fn execute_set_anomaly_config(
     _env: Env,
     info: MessageInfo,
     stk_denom: String,
-    deviation_count_limit: u64,
-    deviation_threshold: Decimal,
+    anomaly_config: AnomalyConfig,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_admin(&config, &info.sender)?;
 
-    let stk_denom_ibc_hash = denom_trace_to_hash(
-        &stk_denom,
-        &config.transfer_port_i_d,
-        &config.transfer_channel_i_d,
-    )?;
+    let stk_denom_ibc_hash = stk_denom_to_hash(deps.storage, &config, &stk_denom)?;
 
-    ANOMALY_CONFIG_BY_DENOM.save(
-        deps.storage,
-        &stk_denom_ibc_hash,
-        &AnomalyConfig {
-            count_limit: deviation_count_limit,
-            threshold: deviation_threshold,
-        },
+    validate_count_limit(
+        anomaly_config.count_limit,
+        get_history_capacity(deps.storage, &stk_denom_ibc_hash)?,
     )?;
 
+    ANOMALY_CONFIG_BY_DENOM.save(deps.storage, &stk_denom_ibc_hash, &anomaly_config)?;
+
     Ok(Response::new()
         .add_attribute("action", "set_anomaly_config")
         .add_attribute("stk_denom", stk_denom)
         .add_attribute("stk_denom_ibc_hash", stk_denom_ibc_hash)
-        .add_attribute("deviation_count_limit", deviation_count_limit.to_string())
-        .add_attribute("deviation_threshold", deviation_threshold.to_string()))
+        .add_attribute(
+            "deviation_count_limit",
+            anomaly_config.count_limit.to_string(),
+        )
+        .add_attribute("deviation_threshold", anomaly_config.threshold.to_string())
+        .add_attribute("threshold_mode", anomaly_config.threshold_mode.to_string())
+        .add_attribute("policy", anomaly_config.policy.to_string())
+        .add_attribute("strategy", anomaly_config.strategy.to_string())
+        .add_attribute(
+            "exclude_flagged",
+            anomaly_config.exclude_flagged.to_string(),
+        ))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-
-        QueryMsg::AnomalyConfig { denom } => to_json_binary(&query_anomaly_config(deps, denom)?),
-
-        QueryMsg::RedemptionRate { denom, params } => {
-            to_json_binary(&get_latest_liquid_stake_rate(deps, denom, params)?)
+// Config that a config message falls back to for the fields it leaves out, so that an
+// update doesn't silently reset them: the config stored for the denom, falling back to
+// the default config
+fn current_anomaly_config(
+    deps: Deps,
+    stk_denom: Option<&str>,
+) -> Result<AnomalyConfig, ContractError> {
+    if let Some(stk_denom) = stk_denom {
+        let config = CONFIG.load(deps.storage)?;
+        let denom = stk_denom_to_hash(deps.storage, &config, stk_denom)?;
+        if let Some(anomaly_config) = ANOMALY_CONFIG_BY_DENOM.may_load(deps.storage, &denom)? {
+            return Ok(anomaly_config);
         }
-
-        QueryMsg::HistoricalRedemptionRates {
-            denom,
-            params,
-            limit,
-            ..
-        } => to_json_binary(&get_historical_liquid_stake_rates(
-            deps, denom, params, limit,
-        )?),
     }
+
+    Ok(get_default_anomaly_config(deps.storage)?)
 }
 
-fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+// Set anomaly config for newly seen denoms
+pub fn execute_set_default_anomaly_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    anomaly_config: AnomalyConfig,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    Ok(ConfigResponse {
-        owner: config.owner,
-        transfer_channel_i_d: config.transfer_channel_i_d,
-        transfer_port_i_d: config.transfer_port_i_d,
-    })
+    ensure_admin(&config, &info.sender)?;
+
+    validate_count_limit(anomaly_config.count_limit, HISTORY_ITEM_CAP)?;
+
+    DEFAULT_ANOMALY_CONFIG.save(deps.storage, &anomaly_config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_default_anomaly_config")
+        .add_attribute(
+            "deviation_count_limit",
+            anomaly_config.count_limit.to_string(),
+        )
+        .add_attribute("deviation_threshold", anomaly_config.threshold.to_string())
+        .add_attribute("threshold_mode", anomaly_config.threshold_mode.to_string())
+        .add_attribute("policy", anomaly_config.policy.to_string())
+        .add_attribute("strategy", anomaly_config.strategy.to_string())
+        .add_attribute(
+            "exclude_flagged",
+            anomaly_config.exclude_flagged.to_string(),
+        ))
 }
 
-fn query_anomaly_config(deps: Deps, denom: String) -> Result<AnomalyConfig, ContractError> {
-    let anomaly_config = ANOMALY_CONFIG_BY_DENOM.load(deps.storage, &denom)?;
+// Set history capacity for denom
+pub fn execute_set_history_capacity(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stk_denom: String,
+    capacity: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    if capacity == 0 {
+        return Err(ContractError::InvalidHistoryCapacity { capacity });
+    }
+
+    let stk_denom_ibc_hash = stk_denom_to_hash(deps.storage, &config, &stk_denom)?;
 
-    Ok(anomaly_config)
+    if let Some(anomaly_config) =
+        ANOMALY_CONFIG_BY_DENOM.may_load(deps.storage, &stk_denom_ibc_hash)?
+    {
+        validate_count_limit(anomaly_config.count_limit, capacity)?;
+    }
+
+    set_history_capacity(deps.storage, &stk_denom_ibc_hash, capacity)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_history_capacity")
+        .add_attribute("stk_denom", stk_denom)
+        .add_attribute("stk_denom_ibc_hash", stk_denom_ibc_hash)
+        .add_attribute("capacity", capacity.to_string()))
 }
 
-pub fn get_latest_liquid_stake_rate(
-    deps: Deps,
-    ibc_denom: String,
-    extra: Option<Binary>,
-) -> Result<RedemptionRateResponse, ContractError> {
-    if extra.is_some() {
-        return Err(ContractError::InvalidQueryRequest {
-            reason: "params must be None".to_string(),
+// Set max age for denom
+pub fn execute_set_max_age(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stk_denom: String,
+    max_age_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let stk_denom_ibc_hash = stk_denom_to_hash(deps.storage, &config, &stk_denom)?;
+
+    match max_age_seconds {
+        Some(0) => return Err(ContractError::InvalidMaxAge { max_age_seconds: 0 }),
+        Some(max_age_seconds) => {
+            MAX_AGE_BY_DENOM.save(deps.storage, &stk_denom_ibc_hash, &max_age_seconds)?
+        }
+        None => MAX_AGE_BY_DENOM.remove(deps.storage, &stk_denom_ibc_hash),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_age")
+        .add_attribute("stk_denom", stk_denom)
+        .add_attribute("stk_denom_ibc_hash", stk_denom_ibc_hash)
+        .add_attribute(
+            "max_age_seconds",
+            max_age_seconds.map_or("none".to_string(), |age| age.to_string()),
+        ))
+}
+
+// Set the quorum feeders have to reach before a rate is committed
+pub fn execute_set_quorum_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    quorum_config: Option<QuorumConfig>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    match &quorum_config {
+        Some(quorum_config) => {
+            quorum_config.validate()?;
+            ensure_quorum_reachable(quorum_config, count_feeders(deps.storage)?)?;
+            QUORUM_CONFIG.save(deps.storage, quorum_config)?;
+        }
+        None => QUORUM_CONFIG.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_quorum_config")
+        .add_attribute(
+            "quorum",
+            quorum_config
+                .as_ref()
+                .map_or("none".to_string(), |c| c.quorum.to_string()),
+        )
+        .add_attribute(
+            "tolerance",
+            quorum_config.map_or("none".to_string(), |c| c.tolerance.to_string()),
+        ))
+}
+
+// Number of addresses with the feeder role. Ratesync channels are left out, as their
+// closing can't be refused when it would leave fewer feeders than the quorum.
+fn count_feeders(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(ROLE_MEMBERS
+        .prefix(Role::Feeder.as_str())
+        .keys(storage, None, None, Order::Ascending)
+        .count() as u64)
+}
+
+fn ensure_quorum_reachable(
+    quorum_config: &QuorumConfig,
+    feeders: u64,
+) -> Result<(), ContractError> {
+    if quorum_config.quorum > feeders {
+        return Err(ContractError::QuorumUnreachable {
+            quorum: quorum_config.quorum,
+            feeders,
         });
     }
 
-    let liquid_stake_rates_history = LIQUID_STAKE_RATES.load(deps.storage, &ibc_denom)?;
+    Ok(())
+}
+
+// Fails if revoking a role would leave fewer feeders than the quorum
+fn ensure_role_revocable(
+    storage: &dyn Storage,
+    role: Role,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    if role != Role::Feeder || !ROLE_MEMBERS.has(storage, (role.as_str(), address)) {
+        return Ok(());
+    }
+
+    match QUORUM_CONFIG.may_load(storage)? {
+        Some(quorum_config) => ensure_quorum_reachable(&quorum_config, count_feeders(storage)? - 1),
+        None => Ok(()),
+    }
+}
+
+// Set transfer path for denom
+pub fn execute_set_transfer_path(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stk_denom: String,
+    transfer_path: TransferPath,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let denom_trace = transfer_path.to_denom_trace(&stk_denom);
+    denom_trace.validate()?;
+    let stk_denom_ibc_hash = denom_trace.ibc_hash()?;
+
+    TRANSFER_PATH_BY_DENOM.save(deps.storage, &stk_denom, &transfer_path)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_transfer_path")
+        .add_attribute("stk_denom", stk_denom)
+        .add_attribute("denom_trace", denom_trace.full_path())
+        .add_attribute("stk_denom_ibc_hash", stk_denom_ibc_hash))
+}
+
+// Remove transfer path for denom
+pub fn execute_remove_transfer_path(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    stk_denom: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    if !TRANSFER_PATH_BY_DENOM.has(deps.storage, &stk_denom) {
+        return Err(ContractError::TransferPathNotFound { stk_denom });
+    }
+    TRANSFER_PATH_BY_DENOM.remove(deps.storage, &stk_denom);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_transfer_path")
+        .add_attribute("stk_denom", stk_denom))
+}
+
+// Propose a new owner
+pub fn execute_propose_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let pending_ownership = propose_ownership(
+        deps.storage,
+        deps.api,
+        &env.block,
+        &config.owner,
+        &info.sender,
+        &new_owner,
+        expires_at,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_ownership")
+        .add_attribute("new_owner", pending_ownership.new_owner)
+        .add_attribute(
+            "expires_at",
+            expires_at.map_or("none".to_string(), |time| time.to_string()),
+        ))
+}
+
+// Accept the pending ownership transfer
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let previous_owner = config.owner;
+    config.owner = accept_ownership(deps.storage, &env.block, &info.sender)?;
+    CONFIG.save(deps.storage, &config)?;
+    move_owner_feeder_role(deps.storage, &previous_owner, &config.owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", config.owner))
+}
+
+// The owner is made a feeder at instantiation, which goes to the new owner along with the
+// ownership unless it was revoked
+fn move_owner_feeder_role(
+    storage: &mut dyn Storage,
+    previous_owner: &Addr,
+    owner: &Addr,
+) -> StdResult<()> {
+    if ROLE_MEMBERS.has(storage, (Role::Feeder.as_str(), previous_owner)) {
+        ROLE_MEMBERS.remove(storage, (Role::Feeder.as_str(), previous_owner));
+        ROLE_MEMBERS.save(storage, (Role::Feeder.as_str(), owner), &Empty {})?;
+    }
+
+    Ok(())
+}
+
+// Cancel the pending ownership transfer
+pub fn execute_cancel_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    cancel_ownership(deps.storage, &config.owner, &info.sender)?;
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership"))
+}
+
+// Grant role to address
+pub fn execute_grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLE_MEMBERS.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address))
+}
+
+// Revoke role from address
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    ensure_role_revocable(deps.storage, role, &address)?;
+    ROLE_MEMBERS.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address))
+}
+
+// Quarantine a served rate
+pub fn execute_quarantine_rate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    update_time: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        ensure_role(deps.storage, Role::Guardian, &info.sender)?;
+    }
+
+    let not_found = || ContractError::RedemptionRateNotFound {
+        denom: denom.clone(),
+        update_time,
+    };
+
+    let mut rate = REDEMPTION_RATES
+        .may_load(deps.storage, (&denom, update_time))?
+        .ok_or_else(not_found)?;
+
+    rate.quarantined = true;
+    REDEMPTION_RATES.save(deps.storage, (&denom, update_time), &rate)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "quarantine_rate")
+        .add_attribute("denom", denom)
+        .add_attribute("update_time", update_time.to_string()))
+}
+
+// Allow ratesync channels on an IBC connection
+pub fn execute_allow_ibc_connection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    connection_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    ALLOWED_IBC_CONNECTIONS.save(deps.storage, &connection_id, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "allow_ibc_connection")
+        .add_attribute("connection_id", connection_id))
+}
+
+// Stop accepting packets from an IBC connection
+pub fn execute_disallow_ibc_connection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    connection_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    ALLOWED_IBC_CONNECTIONS.remove(deps.storage, &connection_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "disallow_ibc_connection")
+        .add_attribute("connection_id", connection_id))
+}
+
+// Pause or resume a denom, or every denom if none is given
+pub fn execute_set_pause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: Option<String>,
+    ingestion: Option<bool>,
+    serving: Option<bool>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        ensure_role(deps.storage, Role::Guardian, &info.sender)?;
+    }
+
+    let flags = update_pause_flags(deps.storage, denom.as_deref(), ingestion, serving)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_pause")
+        .add_attribute("denom", denom.unwrap_or_else(|| "global".to_string()))
+        .add_attribute("ingestion", flags.ingestion.to_string())
+        .add_attribute("serving", flags.serving.to_string()))
+}
+
+// Approve or discard a quarantined rate
+pub fn execute_resolve_quarantined_rate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    update_time: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let not_found = || ContractError::QuarantinedRateNotFound {
+        denom: denom.clone(),
+        update_time,
+    };
+
+    let mut quarantined_rate = REDEMPTION_RATES
+        .may_load(deps.storage, (&denom, update_time))?
+        .filter(|rr| rr.quarantined)
+        .ok_or_else(not_found)?;
+
+    if approve {
+        quarantined_rate.quarantined = false;
+        REDEMPTION_RATES.save(deps.storage, (&denom, update_time), &quarantined_rate)?;
+    } else {
+        remove_rate(deps.storage, &denom, update_time)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "resolve_quarantined_rate")
+        .add_attribute("denom", denom)
+        .add_attribute("update_time", update_time.to_string())
+        .add_attribute("approved", approve.to_string()))
+}
+
+// Remove the history and settings of a denom, optionally leaving a tombstone
+pub fn execute_remove_denom(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    tombstone: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let has_history = HISTORY_HEADS.has(deps.storage, &denom);
+    let retired = RETIRED_DENOMS.has(deps.storage, &denom);
+    if !has_history && !retired && !ANOMALY_CONFIG_BY_DENOM.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotFound { denom });
+    }
+
+    // Removing a retired denom again keeps its original tombstone
+    if !tombstone {
+        RETIRED_DENOMS.remove(deps.storage, &denom);
+    } else if !retired {
+        let tombstone = DenomTombstone {
+            final_rate: find_rate(deps.storage, &denom, Order::Descending, |rr| {
+                !rr.quarantined
+            })?,
+            retired_at: env.block.time.seconds(),
+        };
+        RETIRED_DENOMS.save(deps.storage, &denom, &tombstone)?;
+    }
+
+    clear_history(deps.storage, &denom)?;
+    clear_rounds(deps.storage, &denom)?;
+    let subscribers = SUBSCRIPTIONS
+        .prefix(&denom)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for subscriber in subscribers {
+        SUBSCRIPTIONS.remove(deps.storage, (&denom, &subscriber));
+    }
+    ANOMALY_CONFIG_BY_DENOM.remove(deps.storage, &denom);
+    if let Some(metadata) = DENOM_METADATA.may_load(deps.storage, &denom)? {
+        STK_DENOMS_BY_BOND_DENOM.remove(deps.storage, (&metadata.default_bond_denom, &denom));
+        DENOM_METADATA.remove(deps.storage, &denom);
+    }
+    MAX_AGE_BY_DENOM.remove(deps.storage, &denom);
+    PAUSE_BY_DENOM.remove(deps.storage, &denom);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_denom")
+        .add_attribute("denom", denom)
+        .add_attribute("tombstone", tombstone.to_string()))
+}
+
+// Notify a contract of the new rates of a denom
+pub fn execute_add_subscriber(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    if !SUBSCRIPTIONS.has(deps.storage, (&denom, &address)) {
+        SUBSCRIPTIONS.save(deps.storage, (&denom, &address), &Subscription::default())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "add_subscriber")
+        .add_attribute("denom", denom)
+        .add_attribute("address", address))
+}
+
+// Stop notifying a contract of the new rates of a denom
+pub fn execute_remove_subscriber(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    ensure_admin(&config, &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    if !SUBSCRIPTIONS.has(deps.storage, (&denom, &address)) {
+        return Err(ContractError::SubscriberNotFound {
+            denom,
+            address: address.to_string(),
+        });
+    }
+    SUBSCRIPTIONS.remove(deps.storage, (&denom, &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_subscriber")
+        .add_attribute("denom", denom)
+        .add_attribute("address", address))
+}
+
+/// Records the failure of a subscriber hook. It never fails itself, so that a subscriber
+/// can't revert the rate it was notified of.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    // Hooks only reply on error
+    let SubMsgResult::Err(error) = msg.result else {
+        return Ok(Response::new());
+    };
+
+    let Some(hook) = PENDING_HOOKS
+        .may_load(deps.storage)?
+        .and_then(|hooks| hooks.into_iter().nth(msg.id as usize))
+    else {
+        return Ok(Response::new());
+    };
+
+    let key = (hook.denom.as_str(), &hook.subscriber);
+    if let Some(mut subscription) = SUBSCRIPTIONS.may_load(deps.storage, key)? {
+        subscription.failure_count += 1;
+        subscription.last_failure = Some(HookFailure {
+            update_time: hook.update_time,
+            error: error.clone(),
+            failed_at: env.block.time.seconds(),
+        });
+        SUBSCRIPTIONS.save(deps.storage, key, &subscription)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "rate_hook_failed")
+        .add_attribute("denom", hook.denom)
+        .add_attribute("subscriber", hook.subscriber)
+        .add_attribute("update_time", hook.update_time.to_string())
+        .add_attribute("error", error))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetOwner { owner } => sudo_set_owner(deps, env, owner),
+        SudoMsg::GrantRole { role, address } => sudo_grant_role(deps, env, role, address),
+        SudoMsg::RevokeRole { role, address } => sudo_revoke_role(deps, env, role, address),
+        SudoMsg::SetPause {
+            denom,
+            ingestion,
+            serving,
+        } => sudo_set_pause(deps, env, denom, ingestion, serving),
+        SudoMsg::OverrideRedemptionRate {
+            denom,
+            redemption_rate,
+            update_time,
+        } => sudo_override_redemption_rate(deps, env, denom, redemption_rate, update_time),
+    }
+}
+
+// Replace the owner without the current owner's approval
+pub fn sudo_set_owner(deps: DepsMut, _env: Env, owner: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let previous_owner = config.owner;
+    config.owner = deps.api.addr_validate(&owner)?;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNERSHIP.remove(deps.storage);
+    move_owner_feeder_role(deps.storage, &previous_owner, &config.owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_owner")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", config.owner))
+}
+
+pub fn sudo_grant_role(
+    deps: DepsMut,
+    _env: Env,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    ROLE_MEMBERS.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_grant_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address))
+}
+
+pub fn sudo_revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    ensure_role_revocable(deps.storage, role, &address)?;
+    ROLE_MEMBERS.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_revoke_role")
+        .add_attribute("role", role.to_string())
+        .add_attribute("address", address))
+}
+
+pub fn sudo_set_pause(
+    deps: DepsMut,
+    _env: Env,
+    denom: Option<String>,
+    ingestion: Option<bool>,
+    serving: Option<bool>,
+) -> Result<Response, ContractError> {
+    let flags = update_pause_flags(deps.storage, denom.as_deref(), ingestion, serving)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_pause")
+        .add_attribute("denom", denom.unwrap_or_else(|| "global".to_string()))
+        .add_attribute("ingestion", flags.ingestion.to_string())
+        .add_attribute("serving", flags.serving.to_string()))
+}
+
+// Store a rate marked as overridden, bypassing the pause flags and anomaly detection
+pub fn sudo_override_redemption_rate(
+    deps: DepsMut,
+    _env: Env,
+    denom: String,
+    redemption_rate: Decimal,
+    update_time: u64,
+) -> Result<Response, ContractError> {
+    HISTORY_HEADS.load(deps.storage, &denom)?;
+
+    add_rate(
+        deps.storage,
+        &RedemptionRate {
+            denom: denom.clone(),
+            redemption_rate,
+            update_time,
+            anomaly_detected: false,
+            quarantined: false,
+            overridden: true,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_override_redemption_rate")
+        .add_attribute("denom", denom)
+        .add_attribute("redemption_rate", redemption_rate.to_string())
+        .add_attribute("update_time", update_time.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let info = migrate_contract(deps.storage, CONTRACT_NAME, CONTRACT_VERSION, MIGRATIONS)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut indexed_denoms = vec![];
+    for metadata in msg.denom_metadata {
+        validate_native_denom(&metadata.default_bond_denom)?;
+        let denom = stk_denom_to_hash(deps.storage, &config, &metadata.stk_denom)?;
+
+        // Only stkTokens the contract already tracks can be indexed
+        if !HISTORY_HEADS.has(deps.storage, &denom)
+            && !ANOMALY_CONFIG_BY_DENOM.has(deps.storage, &denom)
+        {
+            return Err(ContractError::DenomNotFound { denom });
+        }
+
+        save_denom_metadata(deps.storage, &denom, &metadata)?;
+        indexed_denoms.push(denom);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", info.from_version)
+        .add_attribute("to_version", info.to_version)
+        .add_attribute("applied_steps", info.applied_steps.join(","))
+        .add_attribute("indexed_denoms", indexed_denoms.join(",")))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+
+        QueryMsg::AnomalyConfig { denom } => to_json_binary(&query_anomaly_config(deps, denom)?),
+
+        QueryMsg::DefaultAnomalyConfig {} => to_json_binary(&query_default_anomaly_config(deps)?),
+
+        QueryMsg::HistoryCapacity { denom } => {
+            to_json_binary(&query_history_capacity(deps, denom)?)
+        }
+
+        QueryMsg::PendingOwnership {} => to_json_binary(&query_pending_ownership(deps.storage)?),
+
+        QueryMsg::RoleMembers { role } => to_json_binary(&query_role_members(deps, role)?),
+
+        QueryMsg::MaxAge { denom } => to_json_binary(&query_max_age(deps, denom)?),
+
+        QueryMsg::RetiredDenom { denom } => {
+            to_json_binary(&RETIRED_DENOMS.load(deps.storage, &denom)?)
+        }
+
+        QueryMsg::Subscribers { denom } => to_json_binary(&query_subscribers(deps, denom)?),
+
+        QueryMsg::QuorumConfig {} => to_json_binary(&QuorumConfigResponse {
+            quorum_config: QUORUM_CONFIG.may_load(deps.storage)?,
+        }),
+
+        QueryMsg::Round { denom, update_time } => {
+            to_json_binary(&ROUNDS.load(deps.storage, (&denom, update_time))?)
+        }
+
+        QueryMsg::IbcConnections {} => to_json_binary(&query_ibc_connections(deps)?),
+
+        QueryMsg::PauseStatus { denom } => to_json_binary(&query_pause_status(deps, denom)?),
+
+        QueryMsg::TransferPath { stk_denom } => {
+            to_json_binary(&query_transfer_path(deps, stk_denom)?)
+        }
+
+        QueryMsg::TransferPaths {} => to_json_binary(&query_transfer_paths(deps)?),
+
+        QueryMsg::DenomInfo { denom } => to_json_binary(&query_denom_info(deps, denom)?),
+
+        QueryMsg::StkDenomsByBondDenom { default_bond_denom } => {
+            to_json_binary(&query_stk_denoms_by_bond_denom(deps, default_bond_denom)?)
+        }
+
+        QueryMsg::RedemptionRate { denom, params } => {
+            to_json_binary(&get_latest_liquid_stake_rate(deps, env, denom, params)?)
+        }
+
+        QueryMsg::Price { denom, .. } => to_json_binary(&query_price(deps, env, denom)?),
+
+        QueryMsg::PriceInfo { denom } => to_json_binary(&query_price_info(deps, env, denom)?),
+
+        QueryMsg::RedemptionRateAt { denom, time, mode } => {
+            to_json_binary(&get_liquid_stake_rate_at(deps, denom, time, mode)?)
+        }
+
+        QueryMsg::AllRedemptionRates { start_after, limit } => {
+            to_json_binary(&query_all_redemption_rates(deps, env, start_after, limit)?)
+        }
+
+        QueryMsg::HistoricalRedemptionRates {
+            denom,
+            params,
+            limit,
+            start_after,
+            end_before,
+            order,
+        } => to_json_binary(&get_historical_liquid_stake_rates(
+            deps,
+            denom,
+            params,
+            limit,
+            start_after,
+            end_before,
+            order,
+        )?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse {
+        owner: config.owner,
+        transfer_channel_i_d: config.transfer_channel_i_d,
+        transfer_port_i_d: config.transfer_port_i_d,
+    })
+}
+
+fn query_anomaly_config(deps: Deps, denom: String) -> Result<AnomalyConfigResponse, ContractError> {
+    let anomaly_config = ANOMALY_CONFIG_BY_DENOM.load(deps.storage, &denom)?;
+
+    Ok(to_anomaly_config_response(anomaly_config))
+}
+
+fn query_default_anomaly_config(deps: Deps) -> StdResult<AnomalyConfigResponse> {
+    let anomaly_config = get_default_anomaly_config(deps.storage)?;
+
+    Ok(to_anomaly_config_response(anomaly_config))
+}
+
+fn to_anomaly_config_response(anomaly_config: AnomalyConfig) -> AnomalyConfigResponse {
+    AnomalyConfigResponse {
+        deviation_count_limit: anomaly_config.count_limit,
+        deviation_threshold: anomaly_config.threshold,
+        threshold_mode: anomaly_config.threshold_mode,
+        policy: anomaly_config.policy,
+        strategy: anomaly_config.strategy,
+        exclude_flagged: anomaly_config.exclude_flagged,
+    }
+}
+
+fn query_history_capacity(
+    deps: Deps,
+    denom: String,
+) -> Result<HistoryCapacityResponse, ContractError> {
+    let head = load_history_head(deps.storage, &denom)?;
+
+    Ok(HistoryCapacityResponse {
+        capacity: head.capacity,
+        count: head.len,
+    })
+}
+
+fn query_denom_info(deps: Deps, denom: String) -> Result<DenomInfoResponse, ContractError> {
+    let Some(metadata) = DENOM_METADATA.may_load(deps.storage, &denom)? else {
+        return Err(ContractError::DenomNotFound { denom });
+    };
+
+    Ok(DenomInfoResponse {
+        denom,
+        stk_denom: metadata.stk_denom,
+        default_bond_denom: metadata.default_bond_denom,
+    })
+}
+
+fn query_stk_denoms_by_bond_denom(
+    deps: Deps,
+    default_bond_denom: String,
+) -> StdResult<StkDenomsResponse> {
+    let stk_denoms = STK_DENOMS_BY_BOND_DENOM
+        .prefix(&default_bond_denom)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|denom| {
+            let denom = denom?;
+            let metadata = DENOM_METADATA.load(deps.storage, &denom)?;
+            Ok(DenomInfoResponse {
+                denom,
+                stk_denom: metadata.stk_denom,
+                default_bond_denom: metadata.default_bond_denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(StkDenomsResponse { stk_denoms })
+}
+
+fn query_subscribers(deps: Deps, denom: String) -> StdResult<SubscribersResponse> {
+    let subscribers = SUBSCRIPTIONS
+        .prefix(&denom)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (address, subscription) = item?;
+            Ok(SubscriberInfo {
+                address,
+                failure_count: subscription.failure_count,
+                last_failure: subscription.last_failure,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SubscribersResponse { subscribers })
+}
+
+fn query_role_members(deps: Deps, role: Role) -> StdResult<RoleMembersResponse> {
+    let members = ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RoleMembersResponse { role, members })
+}
+
+fn query_max_age(deps: Deps, denom: String) -> StdResult<MaxAgeResponse> {
+    Ok(MaxAgeResponse {
+        max_age_seconds: MAX_AGE_BY_DENOM.may_load(deps.storage, &denom)?,
+    })
+}
+
+fn query_ibc_connections(deps: Deps) -> StdResult<IbcConnectionsResponse> {
+    let connection_ids = ALLOWED_IBC_CONNECTIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(IbcConnectionsResponse { connection_ids })
+}
+
+fn query_pause_status(deps: Deps, denom: Option<String>) -> StdResult<PauseStatusResponse> {
+    let global = GLOBAL_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    let denom_flags = denom
+        .map(|denom| PAUSE_BY_DENOM.may_load(deps.storage, &denom))
+        .transpose()?
+        .map(Option::unwrap_or_default);
+    let effective = global.or(denom_flags.unwrap_or_default());
+
+    Ok(PauseStatusResponse {
+        global,
+        denom: denom_flags,
+        ingestion_paused: effective.ingestion,
+        serving_paused: effective.serving,
+    })
+}
+
+fn query_transfer_path(
+    deps: Deps,
+    stk_denom: String,
+) -> Result<TransferPathResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (path, registered) = get_transfer_path(deps.storage, &config, &stk_denom)?;
+
+    to_transfer_path_response(stk_denom, path, registered)
+}
+
+fn query_transfer_paths(deps: Deps) -> Result<TransferPathsResponse, ContractError> {
+    let transfer_paths = TRANSFER_PATH_BY_DENOM
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (stk_denom, path) = item?;
+            to_transfer_path_response(stk_denom, path, true)
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    Ok(TransferPathsResponse { transfer_paths })
+}
+
+fn to_transfer_path_response(
+    stk_denom: String,
+    path: TransferPath,
+    registered: bool,
+) -> Result<TransferPathResponse, ContractError> {
+    let ibc_denom = path.to_denom_trace(&stk_denom).ibc_hash()?;
+
+    Ok(TransferPathResponse {
+        stk_denom,
+        transfer_port_i_d: path.transfer_port_i_d,
+        transfer_channel_i_d: path.transfer_channel_i_d,
+        intermediate_hops: path.intermediate_hops,
+        ibc_denom,
+        registered,
+    })
+}
+
+pub fn get_latest_liquid_stake_rate(
+    deps: Deps,
+    env: Env,
+    ibc_denom: String,
+    extra: Option<Binary>,
+) -> Result<RedemptionRateResponse, ContractError> {
+    let params = parse_redemption_rate_params(extra)?;
+
+    latest_liquid_stake_rate(deps, env, ibc_denom, params)
+}
+
+fn latest_liquid_stake_rate(
+    deps: Deps,
+    env: Env,
+    ibc_denom: String,
+    params: RedemptionRateParams,
+) -> Result<RedemptionRateResponse, ContractError> {
+    let latest = load_served_rate(deps.storage, &ibc_denom)?;
+
+    let redemption_rate = match params.twap_window_seconds {
+        Some(0) => {
+            return Err(ContractError::InvalidQueryRequest {
+                reason: "twap window must be greater than zero".to_string(),
+            })
+        }
+        Some(window_seconds) => {
+            let now = env.block.time.seconds();
+
+            // Only the rates from the latest to the one in effect at the window start count
+            let mut c_value_rates = vec![];
+            for rr in range_rates(deps.storage, &ibc_denom, None, None, Order::Descending) {
+                let rr = rr?;
+                if rr.quarantined {
+                    continue;
+                }
+                let covers_window_start = rr.update_time <= now.saturating_sub(window_seconds);
+                c_value_rates.push(rr);
+                if covers_window_start {
+                    break;
+                }
+            }
+
+            calculate_twap(&c_value_rates, now.saturating_sub(window_seconds), now)?.ok_or_else(
+                || ContractError::TwapWindowNotCovered {
+                    denom: ibc_denom.clone(),
+                    window_seconds,
+                    oldest_update_time: c_value_rates
+                        .last()
+                        .map_or(latest.update_time, |rr| rr.update_time),
+                },
+            )?
+        }
+        None => latest.redemption_rate,
+    };
+
+    // Check the age of the rate against the block time
+    let stale = is_stale(
+        deps.storage,
+        &ibc_denom,
+        latest.update_time,
+        env.block.time.seconds(),
+    )?;
+
+    if stale && params.staleness_mode.unwrap_or_default() == StalenessMode::Strict {
+        return Err(ContractError::StaleRedemptionRate {
+            max_age_seconds: MAX_AGE_BY_DENOM.load(deps.storage, &ibc_denom)?,
+            denom: ibc_denom,
+            update_time: latest.update_time,
+        });
+    }
+
+    Ok(RedemptionRateResponse {
+        redemption_rate,
+        update_time: latest.update_time,
+        stale,
+    })
+}
+
+// Fails if serving the rates of a denom is paused
+fn ensure_serving(storage: &dyn Storage, denom: &str) -> Result<(), ContractError> {
+    if get_pause_flags(storage, denom)?.serving {
+        return Err(ContractError::Paused {
+            denom: denom.to_string(),
+            operation: "serving".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+// Latest non-quarantined rate of a denom, failing if serving is paused
+fn load_served_rate(storage: &dyn Storage, denom: &str) -> Result<RedemptionRate, ContractError> {
+    ensure_serving(storage, denom)?;
+    load_history_head(storage, denom)?;
+
+    find_rate(storage, denom, Order::Descending, |rr| !rr.quarantined)?.ok_or_else(|| {
+        ContractError::InvalidQueryRequest {
+            reason: "liquid stake rate not found".to_string(),
+        }
+    })
+}
+
+pub fn query_price(deps: Deps, env: Env, denom: String) -> Result<PriceResponse, ContractError> {
+    // Lending markets liquidate on this price, so a stale rate always fails the query
+    let params = RedemptionRateParams {
+        staleness_mode: Some(StalenessMode::Strict),
+        twap_window_seconds: None,
+    };
+    let rate = latest_liquid_stake_rate(deps, env, denom.clone(), params)?;
+
+    Ok(PriceResponse {
+        denom,
+        price: rate.redemption_rate,
+    })
+}
+
+pub fn query_price_info(
+    deps: Deps,
+    env: Env,
+    denom: String,
+) -> Result<PriceInfoResponse, ContractError> {
+    let latest = load_served_rate(deps.storage, &denom)?;
+    let now = env.block.time.seconds();
+
+    let confidence = if latest.overridden {
+        Confidence::Overridden
+    } else if latest.anomaly_detected {
+        Confidence::Low
+    } else {
+        Confidence::High
+    };
+
+    Ok(PriceInfoResponse {
+        quote_denom: DENOM_METADATA
+            .may_load(deps.storage, &denom)?
+            .map(|m| m.default_bond_denom),
+        price: latest.redemption_rate,
+        update_time: latest.update_time,
+        age_seconds: now.saturating_sub(latest.update_time),
+        max_age_seconds: MAX_AGE_BY_DENOM.may_load(deps.storage, &denom)?,
+        stale: is_stale(deps.storage, &denom, latest.update_time, now)?,
+        confidence,
+        denom,
+    })
+}
+
+pub fn get_liquid_stake_rate_at(
+    deps: Deps,
+    ibc_denom: String,
+    time: u64,
+    mode: Option<InterpolationMode>,
+) -> Result<RedemptionRateAtResponse, ContractError> {
+    ensure_serving(deps.storage, &ibc_denom)?;
+    load_history_head(deps.storage, &ibc_denom)?;
+
+    let (previous, next) =
+        find_surrounding_rates(deps.storage, &ibc_denom, time, |rr| !rr.quarantined)?;
+
+    let previous = match (previous, &next) {
+        (Some(previous), _) => previous,
+        (None, Some(_)) => return Err(time_outside_history(deps.storage, ibc_denom, time)?),
+        (None, None) => {
+            return Err(ContractError::InvalidQueryRequest {
+                reason: "liquid stake rate not found".to_string(),
+            })
+        }
+    };
+
+    let redemption_rate = match (next, mode.unwrap_or_default()) {
+        _ if previous.update_time == time => previous.redemption_rate,
+        (None, _) => return Err(time_outside_history(deps.storage, ibc_denom, time)?),
+        (Some(_), InterpolationMode::Previous) => previous.redemption_rate,
+        (Some(next), InterpolationMode::Linear) => {
+            let progress = Decimal::from_ratio(
+                time - previous.update_time,
+                next.update_time - previous.update_time,
+            );
+
+            if next.redemption_rate >= previous.redemption_rate {
+                previous.redemption_rate
+                    + (next.redemption_rate - previous.redemption_rate) * progress
+            } else {
+                previous.redemption_rate
+                    - (previous.redemption_rate - next.redemption_rate) * progress
+            }
+        }
+    };
+
+    Ok(RedemptionRateAtResponse {
+        redemption_rate,
+        time,
+        update_time: previous.update_time,
+    })
+}
+
+// Error for a time before the oldest or after the latest served rate
+fn time_outside_history(
+    storage: &dyn Storage,
+    denom: String,
+    time: u64,
+) -> StdResult<ContractError> {
+    let update_time = |order| -> StdResult<u64> {
+        Ok(find_rate(storage, &denom, order, |rr| !rr.quarantined)?
+            .map(|rr| rr.update_time)
+            .unwrap_or_default())
+    };
+
+    Ok(ContractError::TimeOutsideHistory {
+        oldest_update_time: update_time(Order::Ascending)?,
+        latest_update_time: update_time(Order::Descending)?,
+        denom,
+        time,
+    })
+}
+
+pub fn query_all_redemption_rates(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllRedemptionRatesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    // Denoms can have an anomaly config before their first rate is posted
+    let mut denoms = BTreeSet::new();
+    for denom in HISTORY_HEADS
+        .keys(deps.storage, start.clone(), None, Order::Ascending)
+        .take(limit)
+        .chain(
+            ANOMALY_CONFIG_BY_DENOM
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit),
+        )
+    {
+        denoms.insert(denom?);
+    }
+
+    let redemption_rates = denoms
+        .into_iter()
+        .take(limit)
+        .map(|denom| {
+            let metadata = DENOM_METADATA.may_load(deps.storage, &denom)?;
+            // The rate of a denom whose serving is paused is left out
+            let paused = get_pause_flags(deps.storage, &denom)?.serving;
+            let latest = if paused {
+                None
+            } else {
+                find_rate(deps.storage, &denom, Order::Descending, |rr| {
+                    !rr.quarantined
+                })?
+            };
+            let stale = match &latest {
+                Some(rr) => is_stale(
+                    deps.storage,
+                    &denom,
+                    rr.update_time,
+                    env.block.time.seconds(),
+                )?,
+                None => false,
+            };
+
+            Ok(DenomRedemptionRate {
+                stk_denom: metadata.as_ref().map(|m| m.stk_denom.clone()),
+                default_bond_denom: metadata.map(|m| m.default_bond_denom),
+                redemption_rate: latest.as_ref().map(|rr| rr.redemption_rate),
+                update_time: latest.as_ref().map(|rr| rr.update_time),
+                anomaly_detected: latest.is_some_and(|rr| rr.anomaly_detected),
+                stale,
+                paused,
+                denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllRedemptionRatesResponse { redemption_rates })
+}
+
+pub fn get_historical_liquid_stake_rates(
+    deps: Deps,
+    ibc_denom: String,
+    extra: Option<Binary>,
+    limit: Option<u64>,
+    start_after: Option<u64>,
+    end_before: Option<u64>,
+    order: Option<RateOrder>,
+) -> Result<RedemptionRates, ContractError> {
+    if extra.is_some() {
+        return Err(ContractError::InvalidQueryRequest {
+            reason: "params must be None".to_string(),
+        });
+    }
+
+    ensure_serving(deps.storage, &ibc_denom)?;
+    load_history_head(deps.storage, &ibc_denom)?;
+
+    let rates = range_rates(
+        deps.storage,
+        &ibc_denom,
+        start_after,
+        end_before,
+        order.unwrap_or_default().into(),
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT.into()).min(MAX_LIMIT.into()) as usize;
+
+    // Take one more rate than the limit to tell if there is a next page
+    let mut c_value_rates = rates
+        .take(limit.saturating_add(1))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_cursor = if c_value_rates.len() > limit {
+        c_value_rates.pop();
+        c_value_rates.last().map(|rr| rr.update_time)
+    } else {
+        None
+    };
+
+    Ok(RedemptionRates {
+        redemption_rates: c_value_rates,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        attr, coins, from_json, Addr, Empty, OwnedDeps, StdError, Storage, Timestamp,
+    };
+    use ratesync::lsr_helpers::denom_trace_to_hash;
+    use ratesync::lsr_msg::oracle::ActionKind;
+    use ratesync::lsr_msg::RoleMembersResponse;
+    use ratesync::lsr_round::RoundStatus;
+    use ratesync::lsr_state::{
+        AnomalyStrategy, PauseFlags, Role, ThresholdMode, TransferHop, LEGACY_LIQUID_STAKE_RATES,
+    };
+    use ratesync::migrations::MigrationError;
+    use ratesync::ownership::{OwnershipError, PendingOwnership, PendingOwnershipResponse};
+
+    const OWNER_ADDRESS: &str = "creator";
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            admin: Some("owner".to_string()),
+            transfer_channel_i_d: "channel-0".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            deviation_count_limit: None,
+            deviation_threshold: None,
+        };
+        let info = mock_info(OWNER_ADDRESS, &coins(1000, "earth"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("method", "instantiate"),
+                attr("owner", OWNER_ADDRESS.to_string()),
+                attr("transfer_channel_id", "channel-0".to_string()),
+                attr("transfer_port_id", "transfer".to_string()),
+                attr("deviation_count_limit", "10".to_string()),
+                attr("deviation_threshold", "0.05".to_string()),
+            ]
+        );
+
+        // it worked, let's query the state
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(res).unwrap();
+        assert_eq!("owner", value.owner);
+    }
+
+    #[test]
+    fn test_update_config() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            transfer_channel_i_d: Some("channel-1".to_string()),
+            transfer_port_i_d: Some("transfer".to_string()),
+        };
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_config"),
+                attr("transfer_channel_id", "channel-1".to_string()),
+                attr("transfer_port_id", "transfer".to_string()),
+            ]
+        );
+
+        // it worked, let's query the state
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(res).unwrap();
+        assert_eq!("creator", value.owner);
+    }
+
+    // set amnomaly config for denom
+    #[test]
+    fn test_set_anomaly_config() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::SetAnomalyConfig {
+            stk_denom: "somecoin1".to_string(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+
+        let expected_ibc_hash =
+            "ibc/2CC566890930B4BCBA686137D36CDEEFB221AE4726408D7DA0F9B2A40E9CA2AB".to_string();
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_anomaly_config"),
+                attr("stk_denom", "somecoin1".to_string()),
+                attr("stk_denom_ibc_hash", expected_ibc_hash.clone()),
+                attr("deviation_count_limit", "10".to_string()),
+                attr("deviation_threshold", "0.05".to_string()),
+                attr("threshold_mode", "relative".to_string()),
+                attr("policy", "accept_and_flag".to_string()),
+                attr("strategy", "mean".to_string()),
+                attr("exclude_flagged", "false".to_string()),
+            ]
+        );
+
+        // it worked, let's query the state
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AnomalyConfig {
+                denom: expected_ibc_hash,
+            },
+        )
+        .unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(10, value.deviation_count_limit);
+        assert_eq!(Decimal::percent(5), value.deviation_threshold);
+        assert_eq!(ThresholdMode::Relative, value.threshold_mode);
+        assert_eq!(AnomalyPolicy::AcceptAndFlag, value.policy);
+        assert_eq!(AnomalyStrategy::Mean, value.strategy);
+        assert!(!value.exclude_flagged);
+    }
+
+    #[test]
+    fn test_default_anomaly_config() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(OWNER_ADDRESS, &[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            transfer_channel_i_d: "channel-0".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            deviation_count_limit: Some(3),
+            deviation_threshold: Some(Decimal::percent(1)),
+        };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::DefaultAnomalyConfig {},
+        )
+        .unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            AnomalyConfigResponse {
+                deviation_count_limit: 3,
+                deviation_threshold: Decimal::percent(1),
+                threshold_mode: ThresholdMode::Relative,
+                policy: AnomalyPolicy::AcceptAndFlag,
+                strategy: AnomalyStrategy::Mean,
+                exclude_flagged: false,
+            }
+        );
+
+        // Newly seen denoms get the instantiate-time config
+        let msg = get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let ibc_hash_denom = denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap();
+        let msg = QueryMsg::AnomalyConfig {
+            denom: ibc_hash_denom,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.deviation_count_limit, 3);
+        assert_eq!(value.deviation_threshold, Decimal::percent(1));
+
+        let msg = ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit: 20,
+            deviation_threshold: Decimal::percent(2),
+            threshold_mode: None,
+            policy: Some(AnomalyPolicy::Reject),
+            strategy: Some(AnomalyStrategy::Median),
+            exclude_flagged: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_default_anomaly_config"),
+                attr("deviation_count_limit", "20"),
+                attr("deviation_threshold", "0.02"),
+                attr("threshold_mode", "relative"),
+                attr("policy", "reject"),
+                attr("strategy", "median"),
+                attr("exclude_flagged", "false"),
+            ]
+        );
+
+        // Denoms that were already seen keep their config
+        let msg = get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let ibc_hash_denom = denom_trace_to_hash("stk/somecoin2", "transfer", "channel-0").unwrap();
+        let msg = QueryMsg::AnomalyConfig {
+            denom: ibc_hash_denom,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.deviation_count_limit, 20);
+        assert_eq!(value.policy, AnomalyPolicy::Reject);
+        assert_eq!(value.strategy, AnomalyStrategy::Median);
+
+        let msg = get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 2);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let ibc_hash_denom = denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap();
+        let msg = QueryMsg::AnomalyConfig {
+            denom: ibc_hash_denom,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.deviation_count_limit, 3);
+
+        // The default config must fit in the default history capacity
+        let msg = ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit: HISTORY_ITEM_CAP + 1,
+            deviation_threshold: Decimal::percent(2),
+            threshold_mode: None,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::CountLimitExceedsCapacity {
+                count_limit: HISTORY_ITEM_CAP + 1,
+                capacity: HISTORY_ITEM_CAP
+            })
+        );
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_anomaly_strategy() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        let add_rate = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+                        c_value: &str,
+                        time: u64| {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), c_value, time);
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            res.attributes
+                .iter()
+                .any(|attr| attr.key == "anomaly_detected" && attr.value == "true")
+        };
+
+        assert!(!add_rate(&mut deps, "1.0", 1));
+        assert!(add_rate(&mut deps, "1.5", 2));
+
+        // The flagged rate pulls the mean up to 1.25
+        assert!(add_rate(&mut deps, "1.02", 3));
+
+        let msg = ExecuteMsg::SetAnomalyConfig {
+            stk_denom: stk_denom.clone(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy: Some(AnomalyStrategy::Median),
+            exclude_flagged: Some(true),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.attributes.contains(&attr("strategy", "median")));
+        assert!(res.attributes.contains(&attr("exclude_flagged", "true")));
+
+        // Only the first rate is left in the baseline
+        assert!(!add_rate(&mut deps, "1.03", 4));
+        assert!(add_rate(&mut deps, "1.5", 5));
+
+        let msg = QueryMsg::AnomalyConfig {
+            denom: ibc_hash_denom,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(AnomalyStrategy::Median, value.strategy);
+        assert!(value.exclude_flagged);
+    }
+
+    #[test]
+    fn test_unauthorized_update_config() {
+        let (mut deps, env, _info) = default_instantiate();
+
+        // unauthorized attempt
+        let info = mock_info("anyone", &coins(1000, "earth"));
+        let msg = ExecuteMsg::UpdateConfig {
+            transfer_channel_i_d: Some("channel-1".to_string()),
+            transfer_port_i_d: Some("transfer".to_string()),
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // unauthorized attempt for anomaly config
+        let msg = ExecuteMsg::SetAnomalyConfig {
+            stk_denom: "somecoin1".to_string(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+
+        let res = execute(deps.as_mut(), env, info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let (mut deps, env, info) = default_instantiate();
+
+        // UpdateConfig can't change the owner, so it goes through a proposal
+        let expires_at = env.block.time.seconds() + 100;
+        let msg = ExecuteMsg::ProposeOwnership {
+            new_owner: "new_owner".to_string(),
+            expires_at: Some(expires_at),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "propose_ownership"),
+                attr("new_owner", "new_owner"),
+                attr("expires_at", expires_at.to_string()),
+            ]
+        );
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwnership {}).unwrap();
+        let value: PendingOwnershipResponse = from_json(res).unwrap();
+        assert_eq!(
+            value.pending_ownership,
+            Some(PendingOwnership {
+                new_owner: Addr::unchecked("new_owner"),
+                expires_at: Some(expires_at),
+            })
+        );
+
+        // The proposal can't be accepted once it expired
+        let mut expired_env = env.clone();
+        expired_env.block.time = expired_env.block.time.plus_seconds(100);
+        let res = execute(
+            deps.as_mut(),
+            expired_env,
+            mock_info("new_owner", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::Ownership(
+                OwnershipError::OwnershipTransferExpired { expires_at }
+            ))
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("new_owner", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "accept_ownership"),
+                attr("previous_owner", OWNER_ADDRESS),
+                attr("owner", "new_owner"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.owner, "new_owner");
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwnership {}).unwrap();
+        let value: PendingOwnershipResponse = from_json(res).unwrap();
+        assert_eq!(value.pending_ownership, None);
+
+        // The feeder role moved to the new owner
+        let feeders = query_role_members(deps.as_ref(), Role::Feeder).unwrap();
+        assert_eq!(feeders.members, vec![Addr::unchecked("new_owner")]);
+        let msg = get_execute_msg("somecoin".to_string(), "stk/somecoin1".to_string(), "1", 1);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        // The previous owner lost the admin role
+        let msg = ExecuteMsg::UpdateConfig {
+            transfer_channel_i_d: Some("channel-1".to_string()),
+            transfer_port_i_d: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::CancelOwnership {});
+        assert_eq!(
+            res,
+            Err(ContractError::Ownership(OwnershipError::NotOwner {}))
+        );
+    }
+
+    #[test]
+    fn test_denom_info() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let denom_info = |stk_denom: &str, default_bond_denom: &str| DenomInfoResponse {
+            denom: denom_trace_to_hash(stk_denom, "transfer", "channel-0").unwrap(),
+            stk_denom: stk_denom.to_string(),
+            default_bond_denom: default_bond_denom.to_string(),
+        };
+        let bond_denom_query = |deps: Deps, default_bond_denom: &str| {
+            let msg = QueryMsg::StkDenomsByBondDenom {
+                default_bond_denom: default_bond_denom.to_string(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            from_json::<StkDenomsResponse>(res).unwrap().stk_denoms
+        };
+
+        for stk_denom in ["stk/somecoin1", "stk/somecoin2"] {
+            let msg = get_execute_msg("somecoin".to_string(), stk_denom.to_string(), "1", 1);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let somecoin1 = denom_info("stk/somecoin1", "somecoin");
+        let msg = QueryMsg::DenomInfo {
+            denom: somecoin1.denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        assert_eq!(from_json::<DenomInfoResponse>(res).unwrap(), somecoin1);
+
+        let res = query_denom_info(deps.as_ref(), "ibc/unknown".to_string());
+        assert_eq!(
+            res,
+            Err(ContractError::DenomNotFound {
+                denom: "ibc/unknown".to_string()
+            })
+        );
+
+        let mut expected = vec![somecoin1, denom_info("stk/somecoin2", "somecoin")];
+        expected.sort_by(|a, b| a.denom.cmp(&b.denom));
+        assert_eq!(bond_denom_query(deps.as_ref(), "somecoin"), expected);
+        assert_eq!(bond_denom_query(deps.as_ref(), "othercoin"), vec![]);
+
+        // A rate posted with another bond denom moves the stkToken to it
+        let msg = get_execute_msg("othercoin".to_string(), "stk/somecoin1".to_string(), "1", 2);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            bond_denom_query(deps.as_ref(), "somecoin"),
+            vec![denom_info("stk/somecoin2", "somecoin")]
+        );
+        assert_eq!(
+            bond_denom_query(deps.as_ref(), "othercoin"),
+            vec![denom_info("stk/somecoin1", "othercoin")]
+        );
+
+        // Removed denoms leave the index
+        let msg = ExecuteMsg::RemoveDenom {
+            denom: denom_info("stk/somecoin2", "somecoin").denom,
+            tombstone: None,
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(bond_denom_query(deps.as_ref(), "somecoin"), vec![]);
+    }
+
+    #[test]
+    fn test_quorum_rounds() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        for feeder in ["feeder1", "feeder2", "feeder3"] {
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::Feeder,
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let quorum_config = QuorumConfig {
+            quorum: 2,
+            tolerance: Decimal::percent(1),
+        };
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: Some(quorum_config.clone()),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("feeder1", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_quorum_config"),
+                attr("quorum", "2"),
+                attr("tolerance", "0.01"),
+            ]
+        );
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::QuorumConfig {}).unwrap();
+        let value: QuorumConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.quorum_config, Some(quorum_config));
+
+        // The quorum can't exceed the owner and the three feeders
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: Some(QuorumConfig {
+                quorum: 5,
+                tolerance: Decimal::percent(1),
+            }),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::QuorumUnreachable {
+                quorum: 5,
+                feeders: 4,
+            })
+        );
+
+        // Nor can feeders be revoked below it
+        for feeder in ["feeder1", "feeder2"] {
+            let msg = ExecuteMsg::RevokeRole {
+                role: Role::Feeder,
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Feeder,
+            address: "feeder3".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        let expected = Err(ContractError::QuorumUnreachable {
+            quorum: 2,
+            feeders: 1,
+        });
+        assert_eq!(res, expected);
+        let msg = SudoMsg::RevokeRole {
+            role: Role::Feeder,
+            address: "feeder3".to_string(),
+        };
+        assert_eq!(sudo(deps.as_mut(), env.clone(), msg), expected);
+        // Revoking an address that isn't a feeder is always allowed
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Feeder,
+            address: "feeder1".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for feeder in ["feeder1", "feeder2"] {
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::Feeder,
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let submit = |deps: DepsMut, feeder: &str, c_value: &str, time: u64| {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), c_value, time);
+            execute(deps, mock_env(), mock_info(feeder, &[]), msg)
+        };
+
+        // A single submission is buffered rather than served
+        let res = submit(deps.as_mut(), "feeder1", "1", 1).unwrap();
+        assert!(res.attributes.contains(&attr("submissions", "1")));
+        assert!(res.attributes.contains(&attr("committed", "false")));
+        assert!(!HISTORY_HEADS.has(&deps.storage, &ibc_hash_denom));
+
+        let res = submit(deps.as_mut(), "feeder1", "1", 1);
+        assert_eq!(
+            res,
+            Err(ContractError::DuplicateSubmission {
+                denom: ibc_hash_denom.clone(),
+                update_time: 1,
+                feeder: "feeder1".to_string(),
+            })
+        );
+
+        // The median is committed once two submissions agree on it
+        let res = submit(deps.as_mut(), "feeder2", "1.2", 1).unwrap();
+        assert!(res.attributes.contains(&attr("committed", "false")));
+        let res = submit(deps.as_mut(), "feeder3", "1.004", 1).unwrap();
+        assert!(res.attributes.contains(&attr("committed", "true")));
+        assert!(res.attributes.contains(&attr("committed_rate", "1.004")));
+        assert!(res.attributes.contains(&attr("dissenters", "feeder2")));
+
+        let res =
+            get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), ibc_hash_denom.clone(), None);
+        assert_eq!(
+            res.unwrap().redemption_rate,
+            Decimal::from_str("1.004").unwrap()
+        );
+
+        let msg = QueryMsg::Round {
+            denom: ibc_hash_denom.clone(),
+            update_time: 1,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let round: Round = from_json(res).unwrap();
+        assert_eq!(round.submissions.len(), 3);
+        assert_eq!(
+            round.committed_rate,
+            Some(Decimal::from_str("1.004").unwrap())
+        );
+        assert_eq!(round.dissenters, vec![Addr::unchecked("feeder2")]);
+
+        // Batches report the round status of every rate
+        let batch = |deps: DepsMut, feeder: &str, c_value: &str| {
+            let msg = ExecuteMsg::LiquidStakeRates {
+                rates: vec![LiquidStakeRateEntry {
+                    default_bond_denom: "somecoin1".to_string(),
+                    stk_denom: stk_denom.clone(),
+                    c_value: Decimal::from_str(c_value).unwrap(),
+                    controller_chain_time: 2,
+                    denom_trace: None,
+                }],
+            };
+            let res = execute(deps, mock_env(), mock_info(feeder, &[]), msg).unwrap();
+            from_json::<LiquidStakeRatesResponse>(res.data.unwrap())
+                .unwrap()
+                .results
+        };
+        let result = |round_status| LiquidStakeRateResult {
+            stk_denom: stk_denom.clone(),
+            stk_denom_ibc_hash: ibc_hash_denom.clone(),
+            anomaly_detected: false,
+            quarantined: false,
+            round_status: Some(round_status),
+        };
+        assert_eq!(
+            batch(deps.as_mut(), "feeder1", "1.01"),
+            vec![result(RoundStatus::Buffered)]
+        );
+        assert_eq!(
+            batch(deps.as_mut(), "feeder2", "1.01"),
+            vec![result(RoundStatus::Committed)]
+        );
+        assert_eq!(
+            batch(deps.as_mut(), "feeder3", "1.1"),
+            vec![result(RoundStatus::Dissenting)]
+        );
+
+        // Without a quorum, rates are committed as posted again
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = submit(deps.as_mut(), "feeder1", "1.02", 3).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "set_liquid_stake_rate"));
+        let res = get_latest_liquid_stake_rate(deps.as_ref(), env, ibc_hash_denom, None);
+        assert_eq!(res.unwrap().update_time, 3);
+    }
+
+    #[test]
+    fn test_quorum_round_outcomes() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        for feeder in ["feeder1", "feeder2", "feeder3"] {
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::Feeder,
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            10,
+            Decimal::percent(5),
+            Some(AnomalyPolicy::Reject),
+        );
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: Some(QuorumConfig {
+                quorum: 2,
+                tolerance: Decimal::percent(1),
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let submit = |deps: DepsMut, env: Env, feeder: &str, bond_denom: &str, c_value, time| {
+            let msg = get_execute_msg(bond_denom.to_string(), stk_denom.clone(), c_value, time);
+            execute(deps, env, mock_info(feeder, &[]), msg)
+        };
+        let round = |deps: Deps, update_time| {
+            let msg = QueryMsg::Round {
+                denom: ibc_hash_denom.clone(),
+                update_time,
+            };
+            query(deps, mock_env(), msg).map(|res| from_json::<Round>(res).unwrap())
+        };
+
+        // A median refused by the anomaly policy rejects the round instead of failing
+        submit(deps.as_mut(), env.clone(), "feeder1", "somecoin1", "2", 2).unwrap();
+        let res = submit(deps.as_mut(), env.clone(), "feeder2", "somecoin1", "2", 2).unwrap();
+        assert!(res.attributes.contains(&attr("committed", "false")));
+        assert!(res.attributes.contains(&attr("round_status", "rejected")));
+        assert!(round(deps.as_ref(), 2).unwrap().rejected);
+        let res = submit(deps.as_mut(), env.clone(), "feeder3", "somecoin1", "2", 2).unwrap();
+        assert!(res.attributes.contains(&attr("round_status", "rejected")));
+        let res =
+            get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), ibc_hash_denom.clone(), None);
+        assert_eq!(res.unwrap().update_time, 1);
+
+        // Rounds that don't reach the quorum in time are dropped by the next submission
+        submit(deps.as_mut(), env.clone(), "feeder1", "somecoin1", "1", 3).unwrap();
+        let mut later_env = env.clone();
+        later_env.block.time = env.block.time.plus_seconds(ROUND_EXPIRY_SECONDS + 1);
+        submit(
+            deps.as_mut(),
+            later_env.clone(),
+            "feeder1",
+            "somecoin1",
+            "1",
+            4,
+        )
+        .unwrap();
+        assert!(round(deps.as_ref(), 2).is_err());
+        assert!(round(deps.as_ref(), 3).is_err());
+
+        // Only submissions posting the same denoms count toward the quorum
+        let res = submit(
+            deps.as_mut(),
+            later_env.clone(),
+            "feeder2",
+            "othercoin",
+            "1",
+            4,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("committed", "false")));
+        let res = submit(
+            deps.as_mut(),
+            later_env.clone(),
+            "feeder3",
+            "somecoin1",
+            "1",
+            4,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("committed", "true")));
+        assert!(res.attributes.contains(&attr("dissenters", "feeder2")));
+        let res = query_denom_info(deps.as_ref(), ibc_hash_denom).unwrap();
+        assert_eq!(res.default_bond_denom, "somecoin1");
+    }
+
+    #[test]
+    fn test_remove_denom() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        for (c_value, time) in [("1", 1), ("1.01", 2)] {
+            let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), c_value, time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: stk_denom.clone(),
+            max_age_seconds: Some(100),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::RemoveDenom {
+            denom: ibc_hash_denom.clone(),
+            tombstone: Some(true),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let unknown_msg = ExecuteMsg::RemoveDenom {
+            denom: "ibc/unknown".to_string(),
+            tombstone: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), unknown_msg);
+        assert_eq!(
+            res,
+            Err(ContractError::DenomNotFound {
+                denom: "ibc/unknown".to_string()
+            })
+        );
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "remove_denom"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("tombstone", "true"),
+            ]
+        );
+        assert!(!HISTORY_HEADS.has(&deps.storage, &ibc_hash_denom));
+        assert!(
+            range_rates(&deps.storage, &ibc_hash_denom, None, None, Order::Ascending)
+                .next()
+                .is_none()
+        );
+        assert!(!ANOMALY_CONFIG_BY_DENOM.has(&deps.storage, &ibc_hash_denom));
+        assert!(!DENOM_METADATA.has(&deps.storage, &ibc_hash_denom));
+        assert!(!MAX_AGE_BY_DENOM.has(&deps.storage, &ibc_hash_denom));
+
+        // The tombstone keeps the final rate and rate queries report the retirement
+        let retired_at = env.block.time.seconds();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RetiredDenom {
+                denom: ibc_hash_denom.clone(),
+            },
+        )
+        .unwrap();
+        let value: DenomTombstone = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            DenomTombstone {
+                final_rate: Some(get_test_liquid_stake_rate("1.01", 2)),
+                retired_at,
+            }
+        );
+
+        let res =
+            get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), ibc_hash_denom.clone(), None);
+        assert_eq!(
+            res,
+            Err(ContractError::DenomRetired {
+                denom: ibc_hash_denom.clone(),
+                retired_at,
+            })
+        );
+
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "1.02", 3);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::DenomRetired {
+                denom: ibc_hash_denom.clone(),
+                retired_at,
+            })
+        );
+
+        // Removing it without a tombstone lets the denom be registered again
+        let clear_msg = ExecuteMsg::RemoveDenom {
+            denom: ibc_hash_denom.clone(),
+            tombstone: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), clear_msg).unwrap();
+        assert!(!RETIRED_DENOMS.has(&deps.storage, &ibc_hash_denom));
+
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = get_latest_liquid_stake_rate(deps.as_ref(), env, ibc_hash_denom, None).unwrap();
+        assert_eq!(res.redemption_rate, Decimal::from_str("1.02").unwrap());
+        assert_eq!(res.update_time, 3);
+    }
+
+    #[test]
+    fn test_subscribers() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        let add_msg = |address: &str| ExecuteMsg::AddSubscriber {
+            denom: ibc_hash_denom.clone(),
+            address: address.to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            add_msg("subscriber1"),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        for address in ["subscriber1", "subscriber2"] {
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), add_msg(address)).unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "add_subscriber"),
+                    attr("denom", ibc_hash_denom.clone()),
+                    attr("address", address),
+                ]
+            );
+        }
+
+        // Every subscriber gets a hook that only replies on error
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "1", 1);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let hook = to_json_binary(&RateHookMsg::RateUpdated {
+            denom: ibc_hash_denom.clone(),
+            redemption_rate: Decimal::one(),
+            update_time: 1,
+        })
+        .unwrap();
+        let expected_hooks: Vec<SubMsg> = ["subscriber1", "subscriber2"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, address)| {
+                let msg = WasmMsg::Execute {
+                    contract_addr: address.to_string(),
+                    msg: hook.clone(),
+                    funds: vec![],
+                };
+                SubMsg::reply_on_error(msg, id as u64).with_gas_limit(HOOK_GAS_LIMIT)
+            })
+            .collect();
+        assert_eq!(res.messages, expected_hooks);
+
+        // A failing hook is recorded against its subscriber
+        let reply_msg = Reply {
+            id: 1,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "rate_hook_failed"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("subscriber", "subscriber2"),
+                attr("update_time", "1"),
+                attr("error", "out of gas"),
+            ]
+        );
+
+        let msg = QueryMsg::Subscribers {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: SubscribersResponse = from_json(res).unwrap();
+        assert_eq!(
+            value.subscribers,
+            vec![
+                SubscriberInfo {
+                    address: Addr::unchecked("subscriber1"),
+                    failure_count: 0,
+                    last_failure: None,
+                },
+                SubscriberInfo {
+                    address: Addr::unchecked("subscriber2"),
+                    failure_count: 1,
+                    last_failure: Some(HookFailure {
+                        update_time: 1,
+                        error: "out of gas".to_string(),
+                        failed_at: env.block.time.seconds(),
+                    }),
+                },
+            ]
+        );
+
+        // Quarantined rates aren't sent
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            1,
+            Decimal::percent(5),
+            Some(AnomalyPolicy::Quarantine),
+        );
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "2", 2);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let remove_msg = ExecuteMsg::RemoveSubscriber {
+            denom: ibc_hash_denom.clone(),
+            address: "subscriber2".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), remove_msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "remove_subscriber"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("address", "subscriber2"),
+            ]
+        );
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), remove_msg);
+        assert_eq!(
+            res,
+            Err(ContractError::SubscriberNotFound {
+                denom: ibc_hash_denom.clone(),
+                address: "subscriber2".to_string(),
+            })
+        );
+
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom, "1", 3);
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_sudo() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Governance replaces the owner and drops the pending proposal
+        let msg = ExecuteMsg::ProposeOwnership {
+            new_owner: "new_owner".to_string(),
+            expires_at: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = SudoMsg::SetOwner {
+            owner: "governance_owner".to_string(),
+        };
+        let res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "sudo_set_owner"),
+                attr("previous_owner", OWNER_ADDRESS),
+                attr("owner", "governance_owner"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.owner, "governance_owner");
+        let feeders = query_role_members(deps.as_ref(), Role::Feeder).unwrap();
+        assert_eq!(feeders.members, vec![Addr::unchecked("governance_owner")]);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwnership {}).unwrap();
+        let value: PendingOwnershipResponse = from_json(res).unwrap();
+        assert_eq!(value.pending_ownership, None);
+
+        // Roles can be granted and revoked without an admin
+        let msg = SudoMsg::GrantRole {
+            role: Role::Guardian,
+            address: "guardian".to_string(),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        ensure_role(&deps.storage, Role::Guardian, &Addr::unchecked("guardian")).unwrap();
+
+        let msg = SudoMsg::RevokeRole {
+            role: Role::Feeder,
+            address: OWNER_ADDRESS.to_string(),
+        };
+        let res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "sudo_revoke_role"),
+                attr("role", "feeder"),
+                attr("address", OWNER_ADDRESS),
+            ]
+        );
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "1", 2);
+        let res = execute(deps.as_mut(), env.clone(), info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        // Pause serving globally, then lift it
+        let msg = SudoMsg::SetPause {
+            denom: None,
+            ingestion: None,
+            serving: Some(true),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let res =
+            get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), ibc_hash_denom.clone(), None);
+        assert_eq!(
+            res,
+            Err(ContractError::Paused {
+                denom: ibc_hash_denom.clone(),
+                operation: "serving".to_string(),
+            })
+        );
+
+        let msg = SudoMsg::SetPause {
+            denom: None,
+            ingestion: None,
+            serving: Some(false),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+        // An override replaces the latest rate even while ingestion is paused
+        let msg = SudoMsg::SetPause {
+            denom: Some(ibc_hash_denom.clone()),
+            ingestion: Some(true),
+            serving: None,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+        let msg = SudoMsg::OverrideRedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            redemption_rate: Decimal::percent(95),
+            update_time: 1,
+        };
+        let res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "sudo_override_redemption_rate"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("redemption_rate", "0.95"),
+                attr("update_time", "1"),
+            ]
+        );
+
+        let res =
+            get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), ibc_hash_denom.clone(), None)
+                .unwrap();
+        assert_eq!(res.redemption_rate, Decimal::percent(95));
+        assert_eq!(res.update_time, 1);
+
+        let head = HISTORY_HEADS.load(&deps.storage, &ibc_hash_denom).unwrap();
+        assert_eq!(head.len, 1);
+        let rate = REDEMPTION_RATES
+            .load(&deps.storage, (&ibc_hash_denom, 1))
+            .unwrap();
+        assert!(rate.overridden);
+
+        // Only tracked denoms can be overridden
+        let msg = SudoMsg::OverrideRedemptionRate {
+            denom: "ibc/unknown".to_string(),
+            redemption_rate: Decimal::one(),
+            update_time: 1,
+        };
+        let res = sudo(deps.as_mut(), env, msg);
+        assert!(matches!(
+            res,
+            Err(ContractError::Std(StdError::NotFound { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_roles() {
+        let (mut deps, env, info) = default_instantiate();
+
+        // The admin is the initial feeder
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RoleMembers { role: Role::Feeder },
+        )
+        .unwrap();
+        let value: RoleMembersResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            RoleMembersResponse {
+                role: Role::Feeder,
+                members: vec![Addr::unchecked(OWNER_ADDRESS)],
+            }
+        );
+
+        for (role, address) in [(Role::Feeder, "feeder"), (Role::Guardian, "guardian")] {
+            let msg = ExecuteMsg::GrantRole {
+                role,
+                address: address.to_string(),
+            };
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "grant_role"),
+                    attr("role", role.to_string()),
+                    attr("address", address),
+                ]
+            );
+        }
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Feeder,
+            address: OWNER_ADDRESS.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RoleMembers { role: Role::Feeder },
+        )
+        .unwrap();
+        let value: RoleMembersResponse = from_json(res).unwrap();
+        assert_eq!(value.members, vec![Addr::unchecked("feeder")]);
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::RoleMembers {
+                role: Role::Guardian,
+            },
+        )
+        .unwrap();
+        let value: RoleMembersResponse = from_json(res).unwrap();
+        assert_eq!(value.members, vec![Addr::unchecked("guardian")]);
+
+        // Only feeders post rates
+        let stk_denom = "stk/somecoin1".to_string();
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", 1);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), mock_info("feeder", &[]), msg).unwrap();
+
+        // Feeders and guardians can't manage config or roles
+        for sender in ["feeder", "guardian"] {
+            let msg = ExecuteMsg::UpdateConfig {
+                transfer_channel_i_d: Some("channel-1".to_string()),
+                transfer_port_i_d: None,
+            };
+            let res = execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), msg);
+            assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+            let msg = ExecuteMsg::GrantRole {
+                role: Role::Feeder,
+                address: sender.to_string(),
+            };
+            let res = execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), msg);
+            assert_eq!(res, Err(ContractError::Unauthorized {}));
+        }
+    }
+
+    #[test]
+    fn test_guardian_quarantine_rate() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        for time in 1..=2 {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Guardian,
+            address: "guardian".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::QuarantineRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 2,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("guardian", &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "quarantine_rate"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("update_time", "2"),
+            ]
+        );
+
+        // The previous rate is served until the admin resolves the quarantined one
+        let msg = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(value.update_time, 1);
+
+        let resolve_msg = ExecuteMsg::ResolveQuarantinedRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 2,
+            approve: true,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            resolve_msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), info.clone(), resolve_msg).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(value.update_time, 2);
+
+        // The admin can quarantine too
+        let msg = ExecuteMsg::QuarantineRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 3,
+        };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::RedemptionRateNotFound {
+                denom: ibc_hash_denom,
+                update_time: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pause() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom1 = "stk/somecoin1".to_string();
+        let stk_denom2 = "stk/somecoin2".to_string();
+        let ibc_hash_denom1 = denom_trace_to_hash(&stk_denom1, "transfer", "channel-0").unwrap();
+        let ibc_hash_denom2 = denom_trace_to_hash(&stk_denom2, "transfer", "channel-0").unwrap();
+
+        for stk_denom in [&stk_denom1, &stk_denom2] {
+            let msg = get_execute_msg("somecoin".to_string(), stk_denom.clone(), "1", 1);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Guardian,
+            address: "guardian".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Pause ingestion of the first denom only
+        let msg = ExecuteMsg::SetPause {
+            denom: Some(ibc_hash_denom1.clone()),
+            ingestion: Some(true),
+            serving: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("guardian", &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_pause"),
+                attr("denom", ibc_hash_denom1.clone()),
+                attr("ingestion", "true"),
+                attr("serving", "false"),
+            ]
+        );
+
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom1.clone(), "1", 2);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::Paused {
+                denom: ibc_hash_denom1.clone(),
+                operation: "ingestion".to_string(),
+            })
+        );
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom2.clone(), "1", 2);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Rates are still served while ingestion is paused
+        let rate_query = |denom: &str| QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        query(deps.as_ref(), env.clone(), rate_query(&ibc_hash_denom1)).unwrap();
+
+        // Pause serving globally
+        let msg = ExecuteMsg::SetPause {
+            denom: None,
+            ingestion: None,
+            serving: Some(true),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        for denom in [&ibc_hash_denom1, &ibc_hash_denom2] {
+            let res = get_latest_liquid_stake_rate(deps.as_ref(), env.clone(), denom.clone(), None);
+            assert_eq!(
+                res,
+                Err(ContractError::Paused {
+                    denom: denom.clone(),
+                    operation: "serving".to_string(),
+                })
+            );
+
+            let res = get_historical_liquid_stake_rates(
+                deps.as_ref(),
+                denom.clone(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            assert_eq!(
+                res,
+                Err(ContractError::Paused {
+                    denom: denom.clone(),
+                    operation: "serving".to_string(),
+                })
+            );
+
+            let res = get_liquid_stake_rate_at(deps.as_ref(), denom.clone(), 1, None);
+            assert_eq!(
+                res,
+                Err(ContractError::Paused {
+                    denom: denom.clone(),
+                    operation: "serving".to_string(),
+                })
+            );
+        }
+
+        // Paused denoms are listed without their rate
+        let msg = QueryMsg::AllRedemptionRates {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllRedemptionRatesResponse = from_json(res).unwrap();
+        assert_eq!(value.redemption_rates.len(), 2);
+        for rate in value.redemption_rates {
+            assert!(rate.paused);
+            assert_eq!(rate.redemption_rate, None);
+            assert_eq!(rate.update_time, None);
+        }
+
+        let msg = QueryMsg::PauseStatus {
+            denom: Some(ibc_hash_denom1.clone()),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: PauseStatusResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            PauseStatusResponse {
+                global: PauseFlags {
+                    ingestion: false,
+                    serving: true,
+                },
+                denom: Some(PauseFlags {
+                    ingestion: true,
+                    serving: false,
+                }),
+                ingestion_paused: true,
+                serving_paused: true,
+            }
+        );
+
+        let msg = QueryMsg::PauseStatus { denom: None };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: PauseStatusResponse = from_json(res).unwrap();
+        assert!(!value.ingestion_paused);
+        assert!(value.serving_paused);
+        assert_eq!(value.denom, None);
+
+        // Resume everything
+        for denom in [None, Some(ibc_hash_denom1.clone())] {
+            let msg = ExecuteMsg::SetPause {
+                denom,
+                ingestion: Some(false),
+                serving: Some(false),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let msg = get_execute_msg("somecoin".to_string(), stk_denom1, "1", 2);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = query(deps.as_ref(), env, rate_query(&ibc_hash_denom1)).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(value.update_time, 2);
+    }
+
+    #[test]
+    fn set_liq_stake_rate() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin1".to_string(),
+            stk_denom: "somecoin2".to_string(),
+            c_value: Decimal::percent(1),
+            controller_chain_time: 1,
+            denom_trace: None,
+        };
+
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let ibc_hash_denom = denom_trace_to_hash("somecoin2", "transfer", "channel-0").unwrap();
+
+        // it worked, let's query the state
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RedemptionRate {
+                denom: ibc_hash_denom,
+                params: None,
+            },
+        )
+        .unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(Decimal::percent(1), value.redemption_rate);
+    }
+
+    #[test]
+    fn test_liquid_stake_rates_batch() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom1 = "stk/somecoin1".to_string();
+        let stk_denom2 = "stk/somecoin2".to_string();
+        let ibc_hash_denom1 = denom_trace_to_hash(&stk_denom1, "transfer", "channel-0").unwrap();
+        let ibc_hash_denom2 = denom_trace_to_hash(&stk_denom2, "transfer", "channel-0").unwrap();
+
+        let entry = |stk_denom: &str, c_value: &str, time: u64| LiquidStakeRateEntry {
+            default_bond_denom: "somecoin".to_string(),
+            stk_denom: stk_denom.to_string(),
+            c_value: Decimal::from_str(c_value).unwrap(),
+            controller_chain_time: time,
+            denom_trace: None,
+        };
+        let latest_update_time = |deps: Deps, denom: &str| {
+            get_latest_liquid_stake_rate(deps, mock_env(), denom.to_string(), None)
+                .unwrap()
+                .update_time
+        };
+
+        let msg = ExecuteMsg::LiquidStakeRates {
+            rates: vec![entry(&stk_denom1, "1", 1), entry(&stk_denom2, "1", 1)],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The second rate deviates from its history
+        let msg = ExecuteMsg::LiquidStakeRates {
+            rates: vec![entry(&stk_denom1, "1", 2), entry(&stk_denom2, "2", 2)],
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_liquid_stake_rates"),
+                attr("count", "2"),
+                attr("anomalies_detected", "1"),
+            ]
+        );
+        let data: LiquidStakeRatesResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(
+            data.results,
+            vec![
+                LiquidStakeRateResult {
+                    stk_denom: stk_denom1.clone(),
+                    stk_denom_ibc_hash: ibc_hash_denom1.clone(),
+                    anomaly_detected: false,
+                    quarantined: false,
+                    round_status: None,
+                },
+                LiquidStakeRateResult {
+                    stk_denom: stk_denom2.clone(),
+                    stk_denom_ibc_hash: ibc_hash_denom2.clone(),
+                    anomaly_detected: true,
+                    quarantined: false,
+                    round_status: None,
+                },
+            ]
+        );
+        assert_eq!(latest_update_time(deps.as_ref(), &ibc_hash_denom1), 2);
+        assert_eq!(latest_update_time(deps.as_ref(), &ibc_hash_denom2), 2);
+
+        // A single invalid entry rejects the whole batch
+        let mut invalid_entry = entry(&stk_denom2, "1", 3);
+        invalid_entry.default_bond_denom = "1coin".to_string();
+        let msg = ExecuteMsg::LiquidStakeRates {
+            rates: vec![entry(&stk_denom1, "1", 3), invalid_entry],
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidDenom {
+                reason: "First character is not ASCII alphabetic".to_string(),
+            })
+        );
+        assert_eq!(latest_update_time(deps.as_ref(), &ibc_hash_denom1), 2);
+
+        let msg = ExecuteMsg::LiquidStakeRates {
+            rates: vec![entry(&stk_denom1, "1", 3), entry(&stk_denom1, "1", 4)],
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::DuplicateRateBatchEntry {
+                denom: ibc_hash_denom1
+            })
+        );
+
+        let msg = ExecuteMsg::LiquidStakeRates { rates: vec![] };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::EmptyRateBatch {}));
+    }
+
+    #[test]
+    fn set_liquid_stake_rate_should_fail() {
+        let (mut deps, env, _info) = default_instantiate();
+
+        // unauthorized attempt
+        let info = mock_info("anyone", &coins(1000, "earth"));
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin1".to_string(),
+            stk_denom: "somecoin2".to_string(),
+            c_value: Decimal::percent(1),
+            controller_chain_time: 1,
+            denom_trace: None,
+        };
+
+        let res = execute(deps.as_mut(), env, info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn test_historical_queries() {
+        // Instantiate contract
+        let (mut deps, env, info) = default_instantiate();
+
+        let default_bond_denom = "somecoin1".to_string();
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        let msg1 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.01", 1);
+        let msg2 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.02", 2);
+        let msg3 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.03", 2);
+        let msg4 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.04", 3);
+
+        let rr1 = get_test_liquid_stake_rate("1.01", 1);
+        let rr2 = get_test_liquid_stake_rate("1.03", 2);
+        let rr3 = get_test_liquid_stake_rate("1.04", 3);
+
+        // Execute each message out of order, and with msg2 coming before msg3
+        execute(deps.as_mut(), env.clone(), info.clone(), msg2).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg1).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg3).unwrap();
+        execute(deps.as_mut(), env.clone(), info, msg4).unwrap();
+
+        // Check the corresponding liquid stake rate query
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+            limit: None,
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let history_response: RedemptionRates = from_json(resp).unwrap();
+        assert_eq!(
+            history_response,
+            RedemptionRates {
+                redemption_rates: vec![rr3.clone(), rr2.clone(), rr1.clone()],
+                next_cursor: None,
+            }
+        );
+
+        // Check the liquid stake rate query with a limit
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+            limit: Some(2),
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let history_response: RedemptionRates = from_json(resp).unwrap();
+        assert_eq!(
+            history_response,
+            RedemptionRates {
+                redemption_rates: vec![rr3, rr2],
+                next_cursor: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_redemption_rate_at() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        for (c_value, time) in [("1.0", 10), ("1.2", 20), ("1.1", 30), ("1.0", 40)] {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), c_value, time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // The rate at 30 is taken out of service
+        let msg = ExecuteMsg::QuarantineRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 30,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let rate_at = |time: u64, mode: Option<InterpolationMode>| {
+            let msg = QueryMsg::RedemptionRateAt {
+                denom: ibc_hash_denom.clone(),
+                time,
+                mode,
+            };
+            query(deps.as_ref(), env.clone(), msg)
+                .map(|res| from_json::<RedemptionRateAtResponse>(res).unwrap())
+        };
+        let linear = Some(InterpolationMode::Linear);
+
+        assert_eq!(
+            rate_at(15, None).unwrap(),
+            RedemptionRateAtResponse {
+                redemption_rate: Decimal::from_str("1.0").unwrap(),
+                time: 15,
+                update_time: 10,
+            }
+        );
+        assert_eq!(
+            rate_at(15, linear).unwrap().redemption_rate,
+            Decimal::from_str("1.1").unwrap()
+        );
+
+        // Interpolates across the quarantined rate, decreasing from 1.2 to 1.0
+        assert_eq!(
+            rate_at(25, None).unwrap().redemption_rate,
+            Decimal::from_str("1.2").unwrap()
+        );
+        assert_eq!(
+            rate_at(25, linear).unwrap(),
+            RedemptionRateAtResponse {
+                redemption_rate: Decimal::from_str("1.15").unwrap(),
+                time: 25,
+                update_time: 20,
+            }
+        );
+
+        // The bounds of the history are included
+        assert_eq!(rate_at(10, linear).unwrap().update_time, 10);
+        assert_eq!(
+            rate_at(40, linear).unwrap().redemption_rate,
+            Decimal::from_str("1.0").unwrap()
+        );
+
+        for time in [9, 41] {
+            assert_eq!(
+                get_liquid_stake_rate_at(deps.as_ref(), ibc_hash_denom.clone(), time, None),
+                Err(ContractError::TimeOutsideHistory {
+                    denom: ibc_hash_denom.clone(),
+                    time,
+                    oldest_update_time: 10,
+                    latest_update_time: 40,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_all_redemption_rates() {
+        let (mut deps, mut env, info) = default_instantiate();
+
+        let ibc_hash =
+            |stk_denom: &str| denom_trace_to_hash(stk_denom, "transfer", "channel-0").unwrap();
+
+        // Rates for three denoms, the second rate of stk/somecoin2 being anomalous
+        for (stk_denom, c_values) in [
+            ("stk/somecoin1", ["1", "1"]),
+            ("stk/somecoin2", ["1", "2"]),
+            ("stk/somecoin3", ["1", "1"]),
+        ] {
+            for (time, c_value) in c_values.into_iter().enumerate() {
+                let msg = get_execute_msg(
+                    "somecoin".to_string(),
+                    stk_denom.to_string(),
+                    c_value,
+                    time as u64 + 1,
+                );
+                execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            }
+        }
+
+        // stk/somecoin4 only has an anomaly config
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            "stk/somecoin4".to_string(),
+            5,
+            Decimal::percent(5),
+            None,
+        );
+
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: "stk/somecoin3".to_string(),
+            max_age_seconds: Some(10),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = Timestamp::from_seconds(20);
+
+        let mut expected: Vec<DenomRedemptionRate> =
+            ["stk/somecoin1", "stk/somecoin2", "stk/somecoin3"]
+                .into_iter()
+                .map(|stk_denom| DenomRedemptionRate {
+                    denom: ibc_hash(stk_denom),
+                    stk_denom: Some(stk_denom.to_string()),
+                    default_bond_denom: Some("somecoin".to_string()),
+                    redemption_rate: Some(Decimal::one()),
+                    update_time: Some(2),
+                    anomaly_detected: false,
+                    stale: false,
+                    paused: false,
+                })
+                .collect();
+        expected[1].redemption_rate = Some(Decimal::from_str("2").unwrap());
+        expected[1].anomaly_detected = true;
+        expected[2].stale = true;
+        expected.push(DenomRedemptionRate {
+            denom: ibc_hash("stk/somecoin4"),
+            stk_denom: None,
+            default_bond_denom: None,
+            redemption_rate: None,
+            update_time: None,
+            anomaly_detected: false,
+            stale: false,
+            paused: false,
+        });
+        expected.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        let msg = QueryMsg::AllRedemptionRates {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllRedemptionRatesResponse = from_json(res).unwrap();
+        assert_eq!(value.redemption_rates, expected);
+
+        // Page through the denoms two at a time
+        let msg = QueryMsg::AllRedemptionRates {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllRedemptionRatesResponse = from_json(res).unwrap();
+        assert_eq!(value.redemption_rates, expected[..2]);
+
+        let msg = QueryMsg::AllRedemptionRates {
+            start_after: Some(expected[1].denom.clone()),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllRedemptionRatesResponse = from_json(res).unwrap();
+        assert_eq!(value.redemption_rates, expected[2..]);
+
+        let msg = QueryMsg::AllRedemptionRates {
+            start_after: Some(expected[3].denom.clone()),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: AllRedemptionRatesResponse = from_json(res).unwrap();
+        assert_eq!(value.redemption_rates, vec![]);
+    }
+
+    #[test]
+    fn test_historical_queries_pagination() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        for time in 1..=5 {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let history_query = |limit: Option<u64>,
+                             start_after: Option<u64>,
+                             end_before: Option<u64>,
+                             order: Option<RateOrder>| {
+            let msg = QueryMsg::HistoricalRedemptionRates {
+                denom: ibc_hash_denom.clone(),
+                params: None,
+                limit,
+                start_after,
+                end_before,
+                order,
+            };
+            let res: RedemptionRates =
+                from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            let times: Vec<u64> = res
+                .redemption_rates
+                .iter()
+                .map(|rr| rr.update_time)
+                .collect();
+            (times, res.next_cursor)
+        };
+
+        // Walk the history newest first, feeding the cursor back as end_before
+        assert_eq!(
+            history_query(Some(2), None, None, None),
+            (vec![5, 4], Some(4))
+        );
+        assert_eq!(
+            history_query(Some(2), None, Some(4), None),
+            (vec![3, 2], Some(2))
+        );
+        assert_eq!(history_query(Some(2), None, Some(2), None), (vec![1], None));
+
+        // Walk the history oldest first, feeding the cursor back as start_after
+        let ascending = Some(RateOrder::Ascending);
+        assert_eq!(
+            history_query(Some(3), None, None, ascending),
+            (vec![1, 2, 3], Some(3))
+        );
+        assert_eq!(
+            history_query(Some(3), Some(3), None, ascending),
+            (vec![4, 5], None)
+        );
+
+        // A page that ends exactly at the last rate has no cursor
+        assert_eq!(
+            history_query(Some(2), Some(3), None, ascending),
+            (vec![4, 5], None)
+        );
+
+        // Time windows, with both bounds exclusive
+        assert_eq!(
+            history_query(None, Some(1), Some(5), None),
+            (vec![4, 3, 2], None)
+        );
+        assert_eq!(
+            history_query(None, Some(1), Some(5), ascending),
+            (vec![2, 3, 4], None)
+        );
+        assert_eq!(history_query(None, Some(4), Some(2), None), (vec![], None));
+        assert_eq!(history_query(None, Some(5), None, None), (vec![], None));
+    }
+
+    #[test]
+    fn test_historical_queries_limits() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        for time in 1..=40 {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let history_query = |limit: Option<u64>| {
+            let msg = QueryMsg::HistoricalRedemptionRates {
+                denom: ibc_hash_denom.clone(),
+                params: None,
+                limit,
+                start_after: None,
+                end_before: None,
+                order: None,
+            };
+            let res: RedemptionRates =
+                from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            (res.redemption_rates.len(), res.next_cursor)
+        };
+
+        // Without a limit a page holds the default number of rates
+        assert_eq!(history_query(None), (DEFAULT_LIMIT as usize, Some(31)));
+
+        // and larger limits are capped
+        assert_eq!(history_query(Some(35)), (MAX_LIMIT as usize, Some(11)));
+        assert_eq!(
+            history_query(Some(u64::MAX)),
+            (MAX_LIMIT as usize, Some(11))
+        );
+    }
+
+    #[test]
+    fn test_all_latest_msgs() {
+        // Instantiate contract
+        let (mut deps, env, info) = default_instantiate();
+
+        // Build three msgs - each with a new and an old record
+        let msg1_old =
+            get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 0);
+        let msg2_old =
+            get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "2", 0);
+        let msg3_old =
+            get_execute_msg("somecoin3".to_string(), "stk/somecoin3".to_string(), "3", 0);
+
+        let msg1_new =
+            get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 1);
+        let msg2_new =
+            get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "2", 2);
+        let msg3_new =
+            get_execute_msg("somecoin3".to_string(), "stk/somecoin3".to_string(), "3", 3);
+
+        // Execute each message
+        execute(deps.as_mut(), env.clone(), info.clone(), msg1_old).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg2_old).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg3_old).unwrap();
+
+        execute(deps.as_mut(), env.clone(), info.clone(), msg1_new).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg2_new).unwrap();
+        execute(deps.as_mut(), env.clone(), info, msg3_new).unwrap();
+
+        let ibc_hash_denom1 =
+            denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap();
+        let ibc_hash_denom2 =
+            denom_trace_to_hash("stk/somecoin2", "transfer", "channel-0").unwrap();
+        let ibc_hash_denom3 =
+            denom_trace_to_hash("stk/somecoin3", "transfer", "channel-0").unwrap();
+
+        // Confirm all msgs are preset and are sorted
+        let query_msg1 = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom1.clone(),
+            params: None,
+        };
+        let query_msg2 = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom2.clone(),
+            params: None,
+        };
+        let query_msg3 = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom3.clone(),
+            params: None,
+        };
+
+        let resp1 = query(deps.as_ref(), env.clone(), query_msg1).unwrap();
+        let resp2 = query(deps.as_ref(), env.clone(), query_msg2).unwrap();
+        let resp3 = query(deps.as_ref(), env, query_msg3).unwrap();
+
+        let msg_responses1: RedemptionRateResponse = from_json(resp1).unwrap();
+        let msg_responses2: RedemptionRateResponse = from_json(resp2).unwrap();
+        let msg_responses3: RedemptionRateResponse = from_json(resp3).unwrap();
+
+        assert_eq!(
+            msg_responses1,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("1").unwrap(),
+                update_time: 1,
+                stale: false,
+            }
+        );
+        assert_eq!(
+            msg_responses2,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("2").unwrap(),
+                update_time: 2,
+                stale: false,
+            }
+        );
+        assert_eq!(
+            msg_responses3,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("3").unwrap(),
+                update_time: 3,
+                stale: false,
+            }
+        )
+    }
+
+    #[test]
+    fn test_anomaly_detected() {
+        // Instantiate contract
+        let (mut deps, env, info) = default_instantiate();
+
+        let default_bond_denom = "somecoin1".to_string();
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        // add liquid stake rate
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: default_bond_denom.clone(),
+            stk_denom: stk_denom.clone(),
+            c_value: Decimal::percent(1),
+            controller_chain_time: 1,
+            denom_trace: None,
+        };
+
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // it worked, let's query the state
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HistoricalRedemptionRates {
+                denom: ibc_hash_denom.clone(),
+                params: None,
+                limit: None,
+                start_after: None,
+                end_before: None,
+                order: None,
+            },
+        )
+        .unwrap();
+
+        let history_response: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(1, history_response.redemption_rates.len());
+        assert_eq!(
+            Decimal::percent(1),
+            history_response.redemption_rates[0].redemption_rate
+        );
+        assert_eq!(1, history_response.redemption_rates[0].update_time);
+        assert_eq!(false, history_response.redemption_rates[0].anomaly_detected);
+
+        // set anomaly config
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            1,
+            Decimal::percent(1),
+            None,
+        );
+
+        // add liquid stake rate
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: default_bond_denom.clone(),
+            stk_denom: stk_denom.clone(),
+            c_value: Decimal::from_str("1.1").unwrap(),
+            controller_chain_time: 2,
+            denom_trace: None,
+        };
+
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // it worked, let's query the state
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HistoricalRedemptionRates {
+                denom: ibc_hash_denom.clone(),
+                params: None,
+                limit: None,
+                start_after: None,
+                end_before: None,
+                order: None,
+            },
+        )
+        .unwrap();
+
+        let history_response: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(2, history_response.redemption_rates.len());
+        assert_eq!(
+            Decimal::from_str("1.1").unwrap(),
+            history_response.redemption_rates[0].redemption_rate
+        );
+        assert_eq!(2, history_response.redemption_rates[0].update_time);
+        assert_eq!(true, history_response.redemption_rates[0].anomaly_detected);
+    }
+
+    #[test]
+    fn test_anomaly_quarantined() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let default_bond_denom = "somecoin1".to_string();
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        let msg = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.0", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            1,
+            Decimal::percent(1),
+            Some(AnomalyPolicy::Quarantine),
+        );
+
+        // Two anomalous rates are stored but quarantined
+        for (value, time) in [("1.5", 2), ("1.6", 3)] {
+            let msg = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), value, time);
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            assert!(res.attributes.contains(&attr("anomaly_detected", "true")));
+            assert!(res.attributes.contains(&attr("quarantined", "true")));
+        }
+
+        // The query keeps serving the last accepted rate
+        let latest_query = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), latest_query.clone()).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::one(),
+                update_time: 1,
+                stale: false,
+            }
+        );
+
+        // History exposes the quarantined entries
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+            limit: None,
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(3, history.redemption_rates.len());
+        assert!(history.redemption_rates[0].quarantined);
+        assert!(history.redemption_rates[1].quarantined);
+        assert!(!history.redemption_rates[2].quarantined);
+
+        // Discard the newest rate, the query still serves the accepted one
+        let msg_discard = ExecuteMsg::ResolveQuarantinedRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 3,
+            approve: false,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg_discard).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "resolve_quarantined_rate"),
+                attr("denom", ibc_hash_denom.clone()),
+                attr("update_time", "3"),
+                attr("approved", "false"),
+            ]
+        );
+
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(2, history.redemption_rates.len());
+
+        let res = query(deps.as_ref(), env.clone(), latest_query.clone()).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(1, value.update_time);
+
+        // Approve the remaining rate, it is served from now on
+        let msg_approve = ExecuteMsg::ResolveQuarantinedRate {
+            denom: ibc_hash_denom.clone(),
+            update_time: 2,
+            approve: true,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            msg_approve.clone(),
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), latest_query).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("1.5").unwrap(),
+                update_time: 2,
+                stale: false,
+            }
+        );
+
+        // The rate is no longer quarantined
+        let res = execute(deps.as_mut(), env, info, msg_approve);
+        assert_eq!(
+            res,
+            Err(ContractError::QuarantinedRateNotFound {
+                denom: ibc_hash_denom,
+                update_time: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_anomaly_rejected() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let default_bond_denom = "somecoin1".to_string();
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        let msg = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.0", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            1,
+            Decimal::percent(1),
+            Some(AnomalyPolicy::Reject),
+        );
+
+        let msg = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.5", 2);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::AnomalousRedemptionRate {
+                denom: ibc_hash_denom.clone(),
+                value: Decimal::from_str("1.5").unwrap(),
+            })
+        );
+
+        // Rates within the threshold are still accepted
+        let msg = get_execute_msg(default_bond_denom, stk_denom, "1.005", 3);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: ibc_hash_denom,
+            params: None,
+            limit: None,
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(2, history.redemption_rates.len());
+        assert_eq!(3, history.redemption_rates[0].update_time);
+    }
+
+    #[test]
+    fn test_unauthorized_resolve_quarantined_rate() {
+        let (mut deps, env, _info) = default_instantiate();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::ResolveQuarantinedRate {
+            denom: "ibc/denom".to_string(),
+            update_time: 1,
+            approve: true,
+        };
+
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_transfer_paths() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::SetTransferPath {
+            stk_denom: "stk/somecoin2".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            transfer_channel_i_d: "channel-5".to_string(),
+            intermediate_hops: None,
+        };
+        let registered_hash =
+            denom_trace_to_hash("stk/somecoin2", "transfer", "channel-5").unwrap();
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_transfer_path"),
+                attr("stk_denom", "stk/somecoin2"),
+                attr("denom_trace", "transfer/channel-5/stk/somecoin2"),
+                attr("stk_denom_ibc_hash", registered_hash.clone()),
+            ]
+        );
+
+        // Change the config path, the registered denom keeps its own path
+        let msg = ExecuteMsg::UpdateConfig {
+            transfer_channel_i_d: Some("channel-1".to_string()),
+            transfer_port_i_d: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let fallback_hash = denom_trace_to_hash("stk/somecoin1", "transfer", "channel-1").unwrap();
+
+        let msg = get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "2", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        for (denom, rate) in [(&fallback_hash, "1"), (&registered_hash, "2")] {
+            let msg = QueryMsg::RedemptionRate {
+                denom: denom.clone(),
+                params: None,
+            };
+            let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let value: RedemptionRateResponse = from_json(res).unwrap();
+            assert_eq!(Decimal::from_str(rate).unwrap(), value.redemption_rate);
+        }
+
+        // Query the path used by each denom
+        let msg = QueryMsg::TransferPath {
+            stk_denom: "stk/somecoin1".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: TransferPathResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            TransferPathResponse {
+                stk_denom: "stk/somecoin1".to_string(),
+                transfer_port_i_d: "transfer".to_string(),
+                transfer_channel_i_d: "channel-1".to_string(),
+                intermediate_hops: vec![],
+                ibc_denom: fallback_hash,
+                registered: false,
+            }
+        );
+
+        let registered_path = TransferPathResponse {
+            stk_denom: "stk/somecoin2".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            transfer_channel_i_d: "channel-5".to_string(),
+            intermediate_hops: vec![],
+            ibc_denom: registered_hash,
+            registered: true,
+        };
+        let msg = QueryMsg::TransferPath {
+            stk_denom: "stk/somecoin2".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: TransferPathResponse = from_json(res).unwrap();
+        assert_eq!(value, registered_path);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::TransferPaths {}).unwrap();
+        let value: TransferPathsResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            TransferPathsResponse {
+                transfer_paths: vec![registered_path]
+            }
+        );
+
+        // Remove the registered path, the denom falls back to the config
+        let msg = ExecuteMsg::RemoveTransferPath {
+            stk_denom: "stk/somecoin2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::TransferPaths {}).unwrap();
+        let value: TransferPathsResponse = from_json(res).unwrap();
+        assert!(value.transfer_paths.is_empty());
+
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::TransferPathNotFound {
+                stk_denom: "stk/somecoin2".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_transfer_path_invalid() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::SetTransferPath {
+            stk_denom: "stk/somecoin2".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            transfer_channel_i_d: "chan-5".to_string(),
+            intermediate_hops: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidChannelID {
+                channel_id: "chan-5".to_string()
+            })
+        );
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::RemoveTransferPath {
+            stk_denom: "stk/somecoin2".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_multi_hop_transfer_path() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let hops = vec![TransferHop {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-7".to_string(),
+        }];
+        let multi_hop_hash = DenomTrace {
+            path: vec![
+                TransferHop {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-3".to_string(),
+                },
+                hops[0].clone(),
+            ],
+            base_denom: "stk/somecoin1".to_string(),
+        }
+        .ibc_hash()
+        .unwrap();
+        assert_eq!(
+            multi_hop_hash,
+            "ibc/5AE34DE7B3C3B17583AF8BB59F09C6E981F6106F442A27779FDE8509A5EA22CA"
+        );
+
+        let msg = ExecuteMsg::SetTransferPath {
+            stk_denom: "stk/somecoin1".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            transfer_channel_i_d: "channel-3".to_string(),
+            intermediate_hops: Some(hops.clone()),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes[2],
+            attr(
+                "denom_trace",
+                "transfer/channel-3/transfer/channel-7/stk/somecoin1"
+            )
+        );
+
+        // Rates for the denom are stored under the multi-hop hash
+        let msg = get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 1);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: multi_hop_hash.clone(),
+            params: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(Decimal::one(), value.redemption_rate);
+
+        let msg = QueryMsg::TransferPath {
+            stk_denom: "stk/somecoin1".to_string(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: TransferPathResponse = from_json(res).unwrap();
+        assert_eq!(value.intermediate_hops, hops);
+        assert_eq!(value.ibc_denom, multi_hop_hash);
+    }
+
+    #[test]
+    fn test_liquid_stake_rate_with_denom_trace() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let denom_trace = DenomTrace {
+            path: vec![
+                TransferHop {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-3".to_string(),
+                },
+                TransferHop {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-7".to_string(),
+                },
+            ],
+            base_denom: "stk/somecoin1".to_string(),
+        };
+
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin1".to_string(),
+            stk_denom: "stk/somecoin1".to_string(),
+            c_value: Decimal::one(),
+            controller_chain_time: 1,
+            denom_trace: Some(denom_trace.clone()),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.attributes.contains(&attr(
+            "stk_denom_ibc_hash",
+            "ibc/5AE34DE7B3C3B17583AF8BB59F09C6E981F6106F442A27779FDE8509A5EA22CA"
+        )));
+
+        // The trace must describe the submitted stk_denom
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin1".to_string(),
+            stk_denom: "stk/somecoin2".to_string(),
+            c_value: Decimal::one(),
+            controller_chain_time: 1,
+            denom_trace: Some(denom_trace.clone()),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidDenomTrace {
+                reason: "base denom stk/somecoin1 does not match stk_denom stk/somecoin2"
+                    .to_string()
+            })
+        );
+
+        // Every hop is validated
+        let mut invalid_trace = denom_trace;
+        invalid_trace.path[1].channel_id = "chan-7".to_string();
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin1".to_string(),
+            stk_denom: "stk/somecoin1".to_string(),
+            c_value: Decimal::one(),
+            controller_chain_time: 1,
+            denom_trace: Some(invalid_trace),
+        };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidChannelID {
+                channel_id: "chan-7".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_history_capacity() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+
+        for time in 1..=5 {
+            let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", time);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // The default anomaly window doesn't fit in the smaller history
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom: stk_denom.clone(),
+            capacity: 3,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::CountLimitExceedsCapacity {
+                count_limit: 10,
+                capacity: 3
+            })
+        );
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            2,
+            Decimal::percent(5),
+            None,
+        );
+
+        // Shrinking the history drops the oldest rates
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom: stk_denom.clone(),
+            capacity: 3,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_history_capacity"),
+                attr("stk_denom", stk_denom.clone()),
+                attr("stk_denom_ibc_hash", ibc_hash_denom.clone()),
+                attr("capacity", "3"),
+            ]
+        );
+
+        let history_query = QueryMsg::HistoricalRedemptionRates {
+            denom: ibc_hash_denom.clone(),
+            params: None,
+            limit: None,
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), history_query.clone()).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        let times: Vec<u64> = history
+            .redemption_rates
+            .iter()
+            .map(|rr| rr.update_time)
+            .collect();
+        assert_eq!(times, vec![5, 4, 3]);
+
+        // New rates keep the history at capacity
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", 6);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::HistoryCapacity {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: HistoryCapacityResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            HistoryCapacityResponse {
+                capacity: 3,
+                count: 3
+            }
+        );
+
+        let res = query(deps.as_ref(), env.clone(), history_query).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(history.redemption_rates[0].update_time, 6);
+        assert_eq!(history.redemption_rates[2].update_time, 4);
+
+        // The anomaly window can't be larger than the history
+        let msg = ExecuteMsg::SetAnomalyConfig {
+            stk_denom: stk_denom.clone(),
+            deviation_count_limit: 4,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::CountLimitExceedsCapacity {
+                count_limit: 4,
+                capacity: 3
+            })
+        );
+
+        set_anomaly_config(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            stk_denom.clone(),
+            3,
+            Decimal::percent(5),
+            None,
+        );
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom,
+            capacity: 2,
+        };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::CountLimitExceedsCapacity {
+                count_limit: 3,
+                capacity: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_history_capacity_new_denom() {
+        let (mut deps, env, info) = default_instantiate();
 
-    match liquid_stake_rates_history.get_latest() {
-        Some(response) => Ok(RedemptionRateResponse {
-            redemption_rate: response.redemption_rate,
-            update_time: response.update_time,
-        }),
-        None => Err(ContractError::InvalidQueryRequest {
-            reason: "liquid stake rate not found".to_string(),
-        }),
-    }
-}
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
 
-pub fn get_historical_liquid_stake_rates(
-    deps: Deps,
-    ibc_denom: String,
-    extra: Option<Binary>,
-    limit: Option<u64>,
-) -> Result<RedemptionRates, ContractError> {
-    if extra.is_some() {
-        return Err(ContractError::InvalidQueryRequest {
-            reason: "params must be None".to_string(),
-        });
-    }
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom: stk_denom.clone(),
+            capacity: 0,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidHistoryCapacity { capacity: 0 })
+        );
 
-    let liquid_stake_rates_history = LIQUID_STAKE_RATES.load(deps.storage, &ibc_denom)?;
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom: stk_denom.clone(),
+            capacity: 4,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    let c_value_rates = match limit {
-        Some(limit) => liquid_stake_rates_history.get_latest_range(limit as usize),
-        None => liquid_stake_rates_history.get_all(),
-    };
-    Ok(RedemptionRates {
-        redemption_rates: c_value_rates,
-    })
-}
+        // The default anomaly config of the new denom is limited to the history capacity
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom, "1", 1);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+        let msg = QueryMsg::AnomalyConfig {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(4, value.deviation_count_limit);
 
-    use super::*;
-    use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
-    };
-    use cosmwasm_std::{attr, coins, from_json, Empty, OwnedDeps};
+        let msg = QueryMsg::HistoryCapacity {
+            denom: ibc_hash_denom,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: HistoryCapacityResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            HistoryCapacityResponse {
+                capacity: 4,
+                count: 1
+            }
+        );
+    }
 
-    const OWNER_ADDRESS: &str = "creator";
+    #[test]
+    fn test_unauthorized_set_history_capacity() {
+        let (mut deps, env, _info) = default_instantiate();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::SetHistoryCapacity {
+            stk_denom: "stk/somecoin1".to_string(),
+            capacity: 10,
+        };
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
 
     #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
+    fn test_stale_redemption_rate() {
+        let (mut deps, env, info) = default_instantiate();
 
-        let msg = InstantiateMsg {
-            admin: Some("owner".to_string()),
-            transfer_channel_i_d: "channel-0".to_string(),
-            transfer_port_i_d: "transfer".to_string(),
-            deviation_count_limit: None,
-            deviation_threshold: None,
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        let update_time = env.block.time.seconds() - 100;
+
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", update_time);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let query_rate = |deps: Deps, params: Option<RedemptionRateParams>| {
+            query(
+                deps,
+                env.clone(),
+                QueryMsg::RedemptionRate {
+                    denom: ibc_hash_denom.clone(),
+                    params: params.map(|params| to_json_binary(&params).unwrap()),
+                },
+            )
         };
-        let info = mock_info(OWNER_ADDRESS, &coins(1000, "earth"));
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // Rates never go stale without a max age
+        let res = query_rate(deps.as_ref(), None).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert!(!value.stale);
+
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: stk_denom.clone(),
+            max_age_seconds: Some(60),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
         assert_eq!(
             res.attributes,
             vec![
-                attr("method", "instantiate"),
-                attr("owner", OWNER_ADDRESS.to_string()),
-                attr("transfer_channel_id", "channel-0".to_string()),
-                attr("transfer_port_id", "transfer".to_string()),
-                attr("deviation_count_limit", "10".to_string()),
-                attr("deviation_threshold", "0.05".to_string()),
+                attr("action", "set_max_age"),
+                attr("stk_denom", stk_denom.clone()),
+                attr("stk_denom_ibc_hash", ibc_hash_denom.clone()),
+                attr("max_age_seconds", "60"),
             ]
         );
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
-        let value: ConfigResponse = from_json(res).unwrap();
-        assert_eq!("owner", value.owner);
+        let msg = QueryMsg::MaxAge {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: MaxAgeResponse = from_json(res).unwrap();
+        assert_eq!(value.max_age_seconds, Some(60));
+
+        // Strict is the default mode
+        let expected_err: StdError = ContractError::StaleRedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            update_time,
+            max_age_seconds: 60,
+        }
+        .into();
+        let res = query_rate(deps.as_ref(), None);
+        assert_eq!(res.unwrap_err().to_string(), expected_err.to_string());
+        let params = RedemptionRateParams {
+            staleness_mode: Some(StalenessMode::Strict),
+            ..Default::default()
+        };
+        let res = query_rate(deps.as_ref(), Some(params));
+        assert_eq!(res.unwrap_err().to_string(), expected_err.to_string());
+
+        let params = RedemptionRateParams {
+            staleness_mode: Some(StalenessMode::Lenient),
+            ..Default::default()
+        };
+        let res = query_rate(deps.as_ref(), Some(params)).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::one(),
+                update_time,
+                stale: true,
+            }
+        );
+
+        // A rate right at the max age is still fresh
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: stk_denom.clone(),
+            max_age_seconds: Some(100),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let res = query_rate(deps.as_ref(), None).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert!(!value.stale);
+
+        // Removing the max age disables the check
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: stk_denom.clone(),
+            max_age_seconds: Some(10),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom,
+            max_age_seconds: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = query_rate(deps.as_ref(), None).unwrap();
+        let value: RedemptionRateResponse = from_json(res).unwrap();
+        assert!(!value.stale);
+
+        let msg = QueryMsg::MaxAge {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: MaxAgeResponse = from_json(res).unwrap();
+        assert_eq!(value.max_age_seconds, None);
+
+        // Params must be valid RedemptionRateParams
+        let msg = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom,
+            params: Some(Binary::from(b"{\"unknown\":true}")),
+        };
+        let res = query(deps.as_ref(), env, msg);
+        assert!(matches!(res, Err(StdError::GenericErr { msg }) if msg.contains("invalid params")));
     }
 
     #[test]
-    fn test_update_config() {
+    fn test_set_max_age_invalid() {
         let (mut deps, env, info) = default_instantiate();
 
-        let msg = ExecuteMsg::UpdateConfig {
-            transfer_channel_i_d: Some("channel-1".to_string()),
-            transfer_port_i_d: Some("transfer".to_string()),
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: "stk/somecoin1".to_string(),
+            max_age_seconds: Some(0),
         };
-
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
         assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "update_config"),
-                attr("transfer_channel_id", "channel-1".to_string()),
-                attr("transfer_port_id", "transfer".to_string()),
-            ]
+            res,
+            Err(ContractError::InvalidMaxAge { max_age_seconds: 0 })
         );
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
-        let value: ConfigResponse = from_json(res).unwrap();
-        assert_eq!("creator", value.owner);
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
     }
 
-    // set amnomaly config for denom
     #[test]
-    fn test_set_anomaly_config() {
-        let (mut deps, env, info) = default_instantiate();
+    fn test_price_queries() {
+        let (mut deps, mut env, info) = default_instantiate();
 
-        let msg = ExecuteMsg::SetAnomalyConfig {
-            stk_denom: "somecoin1".to_string(),
-            deviation_count_limit: 10,
-            deviation_threshold: Decimal::percent(5),
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        let now = env.block.time.seconds();
+
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", now - 10);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom: stk_denom.clone(),
+            max_age_seconds: Some(100),
         };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        let expected_ibc_hash =
-            "ibc/2CC566890930B4BCBA686137D36CDEEFB221AE4726408D7DA0F9B2A40E9CA2AB".to_string();
+        let msg = QueryMsg::Price {
+            denom: ibc_hash_denom.clone(),
+            kind: Some(ActionKind::Liquidation),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        assert_eq!(
+            from_json::<PriceResponse>(res).unwrap(),
+            PriceResponse {
+                denom: ibc_hash_denom.clone(),
+                price: Decimal::one(),
+            }
+        );
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let msg = QueryMsg::PriceInfo {
+            denom: ibc_hash_denom.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
         assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "set_anomaly_config"),
-                attr("stk_denom", "somecoin1".to_string()),
-                attr("stk_denom_ibc_hash", expected_ibc_hash.clone()),
-                attr("deviation_count_limit", "10".to_string()),
-                attr("deviation_threshold", "0.05".to_string()),
-            ]
+            from_json::<PriceInfoResponse>(res).unwrap(),
+            PriceInfoResponse {
+                denom: ibc_hash_denom.clone(),
+                quote_denom: Some("somecoin1".to_string()),
+                price: Decimal::one(),
+                update_time: now - 10,
+                age_seconds: 10,
+                max_age_seconds: Some(100),
+                stale: false,
+                confidence: Confidence::High,
+            }
         );
 
-        // it worked, let's query the state
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::AnomalyConfig {
-                denom: expected_ibc_hash,
-            },
-        )
-        .unwrap();
-        let value: AnomalyConfig = from_json(res).unwrap();
-        assert_eq!(10, value.count_limit);
-        assert_eq!(Decimal::percent(5), value.threshold);
+        // A flagged rate lowers the confidence and an overridden one reports it
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom, "1.1", now - 5);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let confidence = |deps: Deps, env: Env| {
+            query_price_info(deps, env, ibc_hash_denom.clone())
+                .unwrap()
+                .confidence
+        };
+        assert_eq!(confidence(deps.as_ref(), env.clone()), Confidence::Low);
+
+        let msg = SudoMsg::OverrideRedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            redemption_rate: Decimal::from_str("1.02").unwrap(),
+            update_time: now,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(
+            confidence(deps.as_ref(), env.clone()),
+            Confidence::Overridden
+        );
+
+        // Stale rates fail the price query but are still described
+        env.block.time = env.block.time.plus_seconds(200);
+        let res = query_price(deps.as_ref(), env.clone(), ibc_hash_denom.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::StaleRedemptionRate {
+                denom: ibc_hash_denom.clone(),
+                update_time: now,
+                max_age_seconds: 100,
+            })
+        );
+        let info = query_price_info(deps.as_ref(), env, ibc_hash_denom).unwrap();
+        assert!(info.stale);
+        assert_eq!(info.age_seconds, 200);
     }
 
     #[test]
-    fn test_unauthorized_update_config() {
-        let (mut deps, env, _info) = default_instantiate();
+    fn test_price_fails_on_stale_rate() {
+        let (mut deps, mut env, info) = default_instantiate();
 
-        // unauthorized attempt
-        let info = mock_info("anyone", &coins(1000, "earth"));
-        let msg = ExecuteMsg::UpdateConfig {
-            transfer_channel_i_d: Some("channel-1".to_string()),
-            transfer_port_i_d: Some("transfer".to_string()),
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        let now = env.block.time.seconds();
+
+        let msg = get_execute_msg("somecoin1".to_string(), stk_denom.clone(), "1", now);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetMaxAge {
+            stk_denom,
+            max_age_seconds: Some(100),
         };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return unauthorized error"),
-        }
+        env.block.time = env.block.time.plus_seconds(101);
 
-        // unauthorized attempt for anomaly config
-        let msg = ExecuteMsg::SetAnomalyConfig {
-            stk_denom: "somecoin1".to_string(),
-            deviation_count_limit: 10,
-            deviation_threshold: Decimal::percent(5),
+        // The rate is still available to callers asking for stale rates
+        let params = RedemptionRateParams {
+            staleness_mode: Some(StalenessMode::Lenient),
+            twap_window_seconds: None,
         };
+        let msg = QueryMsg::RedemptionRate {
+            denom: ibc_hash_denom.clone(),
+            params: Some(to_json_binary(&params).unwrap()),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        assert!(from_json::<RedemptionRateResponse>(res).unwrap().stale);
 
-        let res = execute(deps.as_mut(), env, info, msg);
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return unauthorized error"),
+        // But never as a price, whatever the action
+        for kind in [
+            None,
+            Some(ActionKind::Default),
+            Some(ActionKind::Liquidation),
+        ] {
+            let msg = QueryMsg::Price {
+                denom: ibc_hash_denom.clone(),
+                kind,
+            };
+            let res = query(deps.as_ref(), env.clone(), msg);
+            assert_eq!(
+                res,
+                Err(ContractError::StaleRedemptionRate {
+                    denom: ibc_hash_denom.clone(),
+                    update_time: now,
+                    max_age_seconds: 100,
+                }
+                .into())
+            );
         }
     }
 
     #[test]
-    fn set_liq_stake_rate() {
+    fn test_twap_redemption_rate() {
         let (mut deps, env, info) = default_instantiate();
 
-        let msg = ExecuteMsg::LiquidStakeRate {
-            default_bond_denom: "somecoin1".to_string(),
-            stk_denom: "somecoin2".to_string(),
-            c_value: Decimal::percent(1),
-            controller_chain_time: 1,
+        let stk_denom = "stk/somecoin1".to_string();
+        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+        let now = env.block.time.seconds();
+
+        for (c_value, update_time) in [("1.0", now - 300), ("1.1", now - 200), ("1.2", now - 100)] {
+            let msg = get_execute_msg(
+                "somecoin1".to_string(),
+                stk_denom.clone(),
+                c_value,
+                update_time,
+            );
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let query_twap = |deps: Deps, twap_window_seconds: u64| {
+            let params = RedemptionRateParams {
+                twap_window_seconds: Some(twap_window_seconds),
+                ..Default::default()
+            };
+            get_latest_liquid_stake_rate(
+                deps,
+                env.clone(),
+                ibc_hash_denom.clone(),
+                Some(to_json_binary(&params).unwrap()),
+            )
+        };
+
+        // 1.2 * 100 + 1.1 * 100
+        assert_eq!(
+            query_twap(deps.as_ref(), 200),
+            Ok(RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("1.15").unwrap(),
+                update_time: now - 100,
+                stale: false,
+            })
+        );
+        // 1.2 * 100 + 1.1 * 100 + 1.0 * 100
+        assert_eq!(
+            query_twap(deps.as_ref(), 300).unwrap().redemption_rate,
+            Decimal::from_str("1.1").unwrap()
+        );
+
+        assert_eq!(
+            query_twap(deps.as_ref(), 301),
+            Err(ContractError::TwapWindowNotCovered {
+                denom: ibc_hash_denom.clone(),
+                window_seconds: 301,
+                oldest_update_time: now - 300,
+            })
+        );
+        assert_eq!(
+            query_twap(deps.as_ref(), 0),
+            Err(ContractError::InvalidQueryRequest {
+                reason: "twap window must be greater than zero".to_string(),
+            })
+        );
+    }
+
+    // Raw state written by the 1.0.0 contract, used to check that migrations keep it readable
+    const FIXTURE_DENOM: &str =
+        "ibc/2256F6C3E947DED8F20F1535BAC94D00027930FFB659244BFD63F251E97C23CC";
+    const FIXTURE_CONFIG: &str =
+        r#"{"owner":"creator","transfer_channel_i_d":"channel-0","transfer_port_i_d":"transfer"}"#;
+    const FIXTURE_ANOMALY_CONFIG: &str = r#"{"count_limit":10,"threshold":"0.05"}"#;
+    const FIXTURE_HISTORY: &str = r#"{"deque":[{"denom":"ibc/2256F6C3E947DED8F20F1535BAC94D00027930FFB659244BFD63F251E97C23CC","redemption_rate":"1.01","update_time":1,"anomaly_detected":false},{"denom":"ibc/2256F6C3E947DED8F20F1535BAC94D00027930FFB659244BFD63F251E97C23CC","redemption_rate":"1.5","update_time":2,"anomaly_detected":true}],"capacity":100}"#;
+
+    // helper function to write the 1.0.0 state fixtures into storage
+    fn fixture_deps(
+        contract_name: &str,
+        version: &str,
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+        let mut deps = mock_dependencies();
+
+        cw2::set_contract_version(deps.as_mut().storage, contract_name, version).unwrap();
+        deps.storage
+            .set(CONFIG.as_slice(), FIXTURE_CONFIG.as_bytes());
+        deps.storage.set(
+            &ANOMALY_CONFIG_BY_DENOM.key(FIXTURE_DENOM),
+            FIXTURE_ANOMALY_CONFIG.as_bytes(),
+        );
+        deps.storage.set(
+            &LEGACY_LIQUID_STAKE_RATES.key(FIXTURE_DENOM),
+            FIXTURE_HISTORY.as_bytes(),
+        );
+
+        deps
+    }
+
+    #[test]
+    fn test_migrate_from_v1_0_0() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.0.0");
+        let env = mock_env();
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "migrate"));
+        assert_eq!(res.attributes[1], attr("from_version", "1.0.0"));
+        assert_eq!(res.attributes[2], attr("to_version", CONTRACT_VERSION));
+        assert_eq!(
+            res.attributes[3],
+            attr("applied_steps", "1.1.0,1.2.0,1.3.0,1.4.0")
+        );
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Config is still readable
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_json(res).unwrap();
+        assert_eq!(
+            value,
+            ConfigResponse {
+                owner: Addr::unchecked("creator"),
+                transfer_channel_i_d: "channel-0".to_string(),
+                transfer_port_i_d: "transfer".to_string(),
+            }
+        );
+
+        // History is still readable, newest first
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: FIXTURE_DENOM.to_string(),
+            params: None,
+            limit: None,
+            start_after: None,
+            end_before: None,
+            order: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let history: RedemptionRates = from_json(res).unwrap();
+        assert_eq!(history.redemption_rates.len(), 2);
+        assert_eq!(history.redemption_rates[0].update_time, 2);
+        assert!(history.redemption_rates[0].anomaly_detected);
+        assert_eq!(history.redemption_rates[0].denom, FIXTURE_DENOM);
+        assert_eq!(history.redemption_rates[1].update_time, 1);
+
+        // Rates are stored without the denom of their key
+        let raw_rate = deps
+            .storage
+            .get(&REDEMPTION_RATES.key((FIXTURE_DENOM, 1)))
+            .unwrap();
+        assert!(!String::from_utf8(raw_rate).unwrap().contains("denom"));
+
+        // The history blob was split into one entry per rate
+        assert!(!LEGACY_LIQUID_STAKE_RATES.has(deps.as_ref().storage, FIXTURE_DENOM));
+        let msg = QueryMsg::HistoryCapacity {
+            denom: FIXTURE_DENOM.to_string(),
         };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: HistoryCapacityResponse = from_json(res).unwrap();
+        assert_eq!(value.capacity, HISTORY_ITEM_CAP);
+        assert_eq!(value.count, 2);
 
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        // Anomaly config is still readable and keeps comparing in absolute units
+        let anomaly_config = ANOMALY_CONFIG_BY_DENOM
+            .load(deps.as_ref().storage, FIXTURE_DENOM)
+            .unwrap();
+        assert_eq!(anomaly_config.count_limit, 10);
+        assert_eq!(anomaly_config.threshold, Decimal::percent(5));
+        assert_eq!(anomaly_config.threshold_mode, ThresholdMode::Absolute);
 
-        let ibc_hash_denom = denom_trace_to_hash("somecoin2", "transfer", "channel-0").unwrap();
+        // New denoms keep getting the built-in config of 1.0.0
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::DefaultAnomalyConfig {},
+        )
+        .unwrap();
+        let value: AnomalyConfigResponse = from_json(res).unwrap();
+        assert_eq!(value.deviation_count_limit, HISTORY_ITEM_CAP);
+        assert_eq!(value.deviation_threshold, Decimal::percent(5));
+        assert_eq!(value.threshold_mode, ThresholdMode::Absolute);
 
-        // it worked, let's query the state
+        // The owner keeps posting rates as a feeder
         let res = query(
             deps.as_ref(),
-            mock_env(),
-            QueryMsg::RedemptionRate {
-                denom: ibc_hash_denom,
-                params: None,
-            },
+            env.clone(),
+            QueryMsg::RoleMembers { role: Role::Feeder },
         )
         .unwrap();
+        let value: RoleMembersResponse = from_json(res).unwrap();
+        assert_eq!(value.members, vec![Addr::unchecked("creator")]);
+
+        // New rates are appended to the migrated history
+        let info = mock_info("creator", &[]);
+        let msg = get_execute_msg(
+            "somecoin1".to_string(),
+            "stk/somecoin1".to_string(),
+            "1.5",
+            3,
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: FIXTURE_DENOM.to_string(),
+            params: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
         let value: RedemptionRateResponse = from_json(res).unwrap();
-        assert_eq!(Decimal::percent(1), value.redemption_rate);
+        assert_eq!(value.update_time, 3);
     }
 
     #[test]
-    fn set_liquid_stake_rate_should_fail() {
-        let (mut deps, env, _info) = default_instantiate();
-
-        // unauthorized attempt
-        let info = mock_info("anyone", &coins(1000, "earth"));
-        let msg = ExecuteMsg::LiquidStakeRate {
-            default_bond_denom: "somecoin1".to_string(),
-            stk_denom: "somecoin2".to_string(),
-            c_value: Decimal::percent(1),
-            controller_chain_time: 1,
+    fn test_migrate_from_v1_1_0_keeps_threshold_mode() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.1.0");
+        let anomaly_config = AnomalyConfig {
+            count_limit: 10,
+            threshold: Decimal::percent(5),
+            threshold_mode: ThresholdMode::Relative,
+            policy: AnomalyPolicy::AcceptAndFlag,
+            strategy: AnomalyStrategy::Mean,
+            exclude_flagged: false,
         };
+        ANOMALY_CONFIG_BY_DENOM
+            .save(deps.as_mut().storage, FIXTURE_DENOM, &anomaly_config)
+            .unwrap();
 
-        let res = execute(deps.as_mut(), env, info, msg);
-        match res {
-            Err(ContractError::Unauthorized {}) => {}
-            _ => panic!("Must return unauthorized error"),
-        }
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+        assert_eq!(
+            res.attributes[3],
+            attr("applied_steps", "1.2.0,1.3.0,1.4.0")
+        );
+
+        let stored = ANOMALY_CONFIG_BY_DENOM
+            .load(deps.as_ref().storage, FIXTURE_DENOM)
+            .unwrap();
+        assert_eq!(stored, anomaly_config);
     }
 
     #[test]
-    fn test_historical_queries() {
-        // Instantiate contract
-        let (mut deps, env, info) = default_instantiate();
-
-        let default_bond_denom = "somecoin1".to_string();
-        let stk_denom = "stk/somecoin1".to_string();
-        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
+    fn test_migrate_from_v1_0_0_indexes_bond_denoms() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.0.0");
+        let metadata = DenomMetadata {
+            stk_denom: "stk/somecoin1".to_string(),
+            default_bond_denom: "somecoin1".to_string(),
+        };
 
-        let msg1 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.01", 1);
-        let msg2 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.02", 2);
-        let msg3 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.03", 2);
-        let msg4 = get_execute_msg(default_bond_denom.clone(), stk_denom.clone(), "1.04", 3);
+        // 1.0.0 didn't store the denoms of an stkToken, so there is nothing to index
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+        assert_eq!(res.attributes[4], attr("indexed_denoms", ""));
+        let value = query_stk_denoms_by_bond_denom(deps.as_ref(), "somecoin1".to_string());
+        assert_eq!(value.unwrap().stk_denoms, vec![]);
 
-        let rr1 = get_test_liquid_stake_rate("1.01", 1);
-        let rr2 = get_test_liquid_stake_rate("1.03", 2);
-        let rr3 = get_test_liquid_stake_rate("1.04", 3);
+        // unless they are given to the migrate message
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.0.0");
+        let msg = MigrateMsg {
+            denom_metadata: vec![metadata.clone()],
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res.attributes[4], attr("indexed_denoms", FIXTURE_DENOM));
 
-        // Execute each message out of order, and with msg2 coming before msg3
-        execute(deps.as_mut(), env.clone(), info.clone(), msg2).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg1).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg3).unwrap();
-        execute(deps.as_mut(), env.clone(), info, msg4).unwrap();
+        let value = query_stk_denoms_by_bond_denom(deps.as_ref(), "somecoin1".to_string());
+        assert_eq!(
+            value.unwrap().stk_denoms,
+            vec![DenomInfoResponse {
+                denom: FIXTURE_DENOM.to_string(),
+                stk_denom: metadata.stk_denom,
+                default_bond_denom: metadata.default_bond_denom,
+            }]
+        );
 
-        // Check the corresponding liquid stake rate query
-        let msg = QueryMsg::HistoricalRedemptionRates {
-            denom: ibc_hash_denom.clone(),
-            params: None,
-            limit: None,
+        // Untracked stkTokens are refused
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.0.0");
+        let msg = MigrateMsg {
+            denom_metadata: vec![DenomMetadata {
+                stk_denom: "stk/somecoin2".to_string(),
+                default_bond_denom: "somecoin2".to_string(),
+            }],
         };
-        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
-        let history_response: RedemptionRates = from_json(resp).unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), msg);
         assert_eq!(
-            history_response,
-            RedemptionRates {
-                redemption_rates: vec![rr3.clone(), rr2.clone(), rr1.clone()]
-            }
+            res,
+            Err(ContractError::DenomNotFound {
+                denom: denom_trace_to_hash("stk/somecoin2", "transfer", "channel-0").unwrap(),
+            })
         );
+    }
 
-        // Check the liquid stake rate query with a limit
-        let msg = QueryMsg::HistoricalRedemptionRates {
-            denom: ibc_hash_denom.clone(),
-            params: None,
-            limit: Some(2),
+    #[test]
+    fn test_migrate_from_v1_3_0_indexes_bond_denoms() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.3.0");
+        let metadata = DenomMetadata {
+            stk_denom: "stk/somecoin1".to_string(),
+            default_bond_denom: "somecoin1".to_string(),
         };
-        let resp = query(deps.as_ref(), env, msg).unwrap();
-        let history_response: RedemptionRates = from_json(resp).unwrap();
+        DENOM_METADATA
+            .save(deps.as_mut().storage, FIXTURE_DENOM, &metadata)
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+        assert_eq!(res.attributes[3], attr("applied_steps", "1.4.0"));
+
+        let value = query_stk_denoms_by_bond_denom(deps.as_ref(), "somecoin1".to_string());
         assert_eq!(
-            history_response,
-            RedemptionRates {
-                redemption_rates: vec![rr3, rr2]
-            }
+            value.unwrap().stk_denoms,
+            vec![DenomInfoResponse {
+                denom: FIXTURE_DENOM.to_string(),
+                stk_denom: metadata.stk_denom,
+                default_bond_denom: metadata.default_bond_denom,
+            }]
         );
     }
 
     #[test]
-    fn test_all_latest_msgs() {
-        // Instantiate contract
+    fn test_anomaly_config_keeps_threshold_mode() {
         let (mut deps, env, info) = default_instantiate();
 
-        // Build three msgs - each with a new and an old record
-        let msg1_old =
-            get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 0);
-        let msg2_old =
-            get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "2", 0);
-        let msg3_old =
-            get_execute_msg("somecoin3".to_string(), "stk/somecoin3".to_string(), "3", 0);
+        let set_anomaly_config = |stk_denom: &str, threshold_mode| ExecuteMsg::SetAnomalyConfig {
+            stk_denom: stk_denom.to_string(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+        let stored_mode = |deps: Deps, stk_denom: &str| {
+            let denom = denom_trace_to_hash(stk_denom, "transfer", "channel-0").unwrap();
+            ANOMALY_CONFIG_BY_DENOM
+                .load(deps.storage, &denom)
+                .unwrap()
+                .threshold_mode
+        };
 
-        let msg1_new =
-            get_execute_msg("somecoin1".to_string(), "stk/somecoin1".to_string(), "1", 1);
-        let msg2_new =
-            get_execute_msg("somecoin2".to_string(), "stk/somecoin2".to_string(), "2", 2);
-        let msg3_new =
-            get_execute_msg("somecoin3".to_string(), "stk/somecoin3".to_string(), "3", 3);
+        // Leaving the mode out keeps the one stored for the denom
+        let msg = set_anomaly_config("somecoin1", Some(ThresholdMode::Absolute));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_anomaly_config("somecoin1", None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            stored_mode(deps.as_ref(), "somecoin1"),
+            ThresholdMode::Absolute
+        );
 
-        // Execute each message
-        execute(deps.as_mut(), env.clone(), info.clone(), msg1_old).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg2_old).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg3_old).unwrap();
+        // and new denoms take the mode of the default config
+        let msg = ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: Some(ThresholdMode::Absolute),
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_anomaly_config("somecoin2", None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            stored_mode(deps.as_ref(), "somecoin2"),
+            ThresholdMode::Absolute
+        );
 
-        execute(deps.as_mut(), env.clone(), info.clone(), msg1_new).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg2_new).unwrap();
-        execute(deps.as_mut(), env.clone(), info, msg3_new).unwrap();
+        let msg = ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit: 20,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy: None,
+            exclude_flagged: None,
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let default_config = DEFAULT_ANOMALY_CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(default_config.threshold_mode, ThresholdMode::Absolute);
+    }
 
-        let ibc_hash_denom1 =
-            denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap();
-        let ibc_hash_denom2 =
-            denom_trace_to_hash("stk/somecoin2", "transfer", "channel-0").unwrap();
-        let ibc_hash_denom3 =
-            denom_trace_to_hash("stk/somecoin3", "transfer", "channel-0").unwrap();
+    #[test]
+    fn test_anomaly_config_keeps_policy() {
+        let (mut deps, env, info) = default_instantiate();
 
-        // Confirm all msgs are preset and are sorted
-        let query_msg1 = QueryMsg::RedemptionRate {
-            denom: ibc_hash_denom1.clone(),
-            params: None,
-        };
-        let query_msg2 = QueryMsg::RedemptionRate {
-            denom: ibc_hash_denom2.clone(),
-            params: None,
+        let set_anomaly_config = |policy| ExecuteMsg::SetAnomalyConfig {
+            stk_denom: "somecoin1".to_string(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy,
+            strategy: None,
+            exclude_flagged: None,
         };
-        let query_msg3 = QueryMsg::RedemptionRate {
-            denom: ibc_hash_denom3.clone(),
-            params: None,
+        let set_default_anomaly_config = |policy| ExecuteMsg::SetDefaultAnomalyConfig {
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy,
+            strategy: None,
+            exclude_flagged: None,
         };
+        let denom = denom_trace_to_hash("somecoin1", "transfer", "channel-0").unwrap();
 
-        let resp1 = query(deps.as_ref(), env.clone(), query_msg1).unwrap();
-        let resp2 = query(deps.as_ref(), env.clone(), query_msg2).unwrap();
-        let resp3 = query(deps.as_ref(), env, query_msg3).unwrap();
-
-        let msg_responses1: RedemptionRateResponse = from_json(resp1).unwrap();
-        let msg_responses2: RedemptionRateResponse = from_json(resp2).unwrap();
-        let msg_responses3: RedemptionRateResponse = from_json(resp3).unwrap();
+        // Changing only the threshold keeps the policy stored for the denom
+        let msg = set_anomaly_config(Some(AnomalyPolicy::Reject));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_anomaly_config(None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let anomaly_config = ANOMALY_CONFIG_BY_DENOM.load(&deps.storage, &denom).unwrap();
+        assert_eq!(anomaly_config.policy, AnomalyPolicy::Reject);
 
-        assert_eq!(
-            msg_responses1,
-            RedemptionRateResponse {
-                redemption_rate: Decimal::from_str("1").unwrap(),
-                update_time: 1,
-            }
-        );
-        assert_eq!(
-            msg_responses2,
-            RedemptionRateResponse {
-                redemption_rate: Decimal::from_str("2").unwrap(),
-                update_time: 2,
-            }
-        );
-        assert_eq!(
-            msg_responses3,
-            RedemptionRateResponse {
-                redemption_rate: Decimal::from_str("3").unwrap(),
-                update_time: 3,
-            }
-        )
+        // and the policy of the default config
+        let msg = set_default_anomaly_config(Some(AnomalyPolicy::Quarantine));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_default_anomaly_config(None);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let default_config = DEFAULT_ANOMALY_CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(default_config.policy, AnomalyPolicy::Quarantine);
     }
 
     #[test]
-    fn test_anomaly_detected() {
-        // Instantiate contract
+    fn test_anomaly_config_keeps_strategy() {
         let (mut deps, env, info) = default_instantiate();
 
-        let default_bond_denom = "somecoin1".to_string();
-        let stk_denom = "stk/somecoin1".to_string();
-        let ibc_hash_denom = denom_trace_to_hash(&stk_denom, "transfer", "channel-0").unwrap();
-
-        // add liquid stake rate
-        let msg = ExecuteMsg::LiquidStakeRate {
-            default_bond_denom: default_bond_denom.clone(),
-            stk_denom: stk_denom.clone(),
-            c_value: Decimal::percent(1),
-            controller_chain_time: 1,
+        let set_anomaly_config = |strategy, exclude_flagged| ExecuteMsg::SetAnomalyConfig {
+            stk_denom: "somecoin1".to_string(),
+            deviation_count_limit: 10,
+            deviation_threshold: Decimal::percent(5),
+            threshold_mode: None,
+            policy: None,
+            strategy,
+            exclude_flagged,
         };
+        let set_default_anomaly_config =
+            |strategy, exclude_flagged| ExecuteMsg::SetDefaultAnomalyConfig {
+                deviation_count_limit: 10,
+                deviation_threshold: Decimal::percent(5),
+                threshold_mode: None,
+                policy: None,
+                strategy,
+                exclude_flagged,
+            };
+        let denom = denom_trace_to_hash("somecoin1", "transfer", "channel-0").unwrap();
 
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        // Changing only the threshold keeps the strategy stored for the denom
+        let msg = set_anomaly_config(Some(AnomalyStrategy::Median), Some(true));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_anomaly_config(None, None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let anomaly_config = ANOMALY_CONFIG_BY_DENOM.load(&deps.storage, &denom).unwrap();
+        assert_eq!(anomaly_config.strategy, AnomalyStrategy::Median);
+        assert!(anomaly_config.exclude_flagged);
 
-        // it worked, let's query the state
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::HistoricalRedemptionRates {
-                denom: ibc_hash_denom.clone(),
-                params: None,
-                limit: None,
-            },
-        )
-        .unwrap();
+        // and the strategy of the default config
+        let msg = set_default_anomaly_config(Some(AnomalyStrategy::ZScore), Some(true));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = set_default_anomaly_config(None, None);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let default_config = DEFAULT_ANOMALY_CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(default_config.strategy, AnomalyStrategy::ZScore);
+        assert!(default_config.exclude_flagged);
+    }
 
-        let history_response: RedemptionRates = from_json(res).unwrap();
-        assert_eq!(1, history_response.redemption_rates.len());
-        assert_eq!(
-            Decimal::percent(1),
-            history_response.redemption_rates[0].redemption_rate
-        );
-        assert_eq!(1, history_response.redemption_rates[0].update_time);
-        assert_eq!(false, history_response.redemption_rates[0].anomaly_detected);
+    #[test]
+    fn test_migrate_refuses_downgrade() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "99.0.0");
 
-        // set anomaly config
-        set_anomaly_config(
-            &mut deps,
-            env.clone(),
-            info.clone(),
-            stk_denom.clone(),
-            1,
-            Decimal::percent(1),
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default());
+        assert_eq!(
+            res,
+            Err(ContractError::Migration(MigrationError::CannotDowngrade {
+                stored: "99.0.0".to_string(),
+                current: CONTRACT_VERSION.to_string(),
+            }))
         );
+    }
 
-        // add liquid stake rate
-        let msg = ExecuteMsg::LiquidStakeRate {
-            default_bond_denom: default_bond_denom.clone(),
-            stk_denom: stk_denom.clone(),
-            c_value: Decimal::from_str("1.1").unwrap(),
-            controller_chain_time: 2,
-        };
-
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-        // it worked, let's query the state
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::HistoricalRedemptionRates {
-                denom: ibc_hash_denom.clone(),
-                params: None,
-                limit: None,
-            },
-        )
-        .unwrap();
+    #[test]
+    fn test_migrate_refuses_other_contract() {
+        let mut deps = fixture_deps("crates.io:osmosis-pool-ratesync", "1.0.0");
 
-        let history_response: RedemptionRates = from_json(res).unwrap();
-        assert_eq!(2, history_response.redemption_rates.len());
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default());
         assert_eq!(
-            Decimal::from_str("1.1").unwrap(),
-            history_response.redemption_rates[0].redemption_rate
+            res,
+            Err(ContractError::Migration(
+                MigrationError::InvalidContractName {
+                    expected: CONTRACT_NAME.to_string(),
+                    actual: "crates.io:osmosis-pool-ratesync".to_string(),
+                }
+            ))
         );
-        assert_eq!(2, history_response.redemption_rates[0].update_time);
-        assert_eq!(true, history_response.redemption_rates[0].anomaly_detected);
     }
 
     // helper function to instantiate contract
@@ -777,6 +5895,8 @@ mod tests {
             redemption_rate: Decimal::from_str(value).unwrap(),
             update_time: time,
             anomaly_detected: false,
+            quarantined: false,
+            overridden: false,
         }
     }
 
@@ -792,6 +5912,7 @@ mod tests {
             stk_denom,
             c_value: Decimal::from_str(c_value).unwrap(),
             controller_chain_time,
+            denom_trace: None,
         }
     }
 
@@ -803,11 +5924,16 @@ mod tests {
         stk_denom: String,
         deviation_count_limit: u64,
         deviation_threshold: Decimal,
+        policy: Option<AnomalyPolicy>,
     ) {
         let msg = ExecuteMsg::SetAnomalyConfig {
             stk_denom,
             deviation_count_limit,
             deviation_threshold,
+            threshold_mode: None,
+            policy,
+            strategy: None,
+            exclude_flagged: None,
         };
 
         let _res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -0,0 +1,542 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Never, SubMsg,
+};
+
+use ratesync::{
+    lsr_error::ContractError,
+    lsr_ibc::{RatesyncAck, RatesyncPacket, RATESYNC_ORDERING, RATESYNC_VERSION},
+    lsr_msg::LiquidStakeRatesResponse,
+    lsr_round::QUORUM_CONFIG,
+    lsr_state::{ALLOWED_IBC_CONNECTIONS, CONFIG, IBC_CHANNELS},
+};
+
+use crate::contract::{ingest_liquid_stake_rates, submit_liquid_stake_rates};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(deps.as_ref(), msg.channel(), msg.counterparty_version())?;
+
+    Ok(None)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(deps.as_ref(), channel, msg.counterparty_version())?;
+
+    IBC_CHANNELS.save(
+        deps.storage,
+        &channel.endpoint.channel_id,
+        &channel.connection_id,
+    )?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id)
+        .add_attribute("connection_id", &channel.connection_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    IBC_CHANNELS.remove(deps.storage, &channel.endpoint.channel_id);
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+/// Errors are returned as an error acknowledgement instead of failing the transaction,
+/// nothing is stored in that case
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    let channel_id = msg.packet.dest.channel_id.clone();
+
+    match receive_packet(deps, env, msg.packet) {
+        Ok((response, hooks)) => {
+            let anomalies = response
+                .results
+                .iter()
+                .filter(|r| r.anomaly_detected)
+                .count();
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack(&RatesyncAck::Result(response.clone())))
+                .add_submessages(hooks)
+                .add_attribute("action", "receive_liquid_stake_rates")
+                .add_attribute("channel_id", channel_id)
+                .add_attribute("count", response.results.len().to_string())
+                .add_attribute("anomalies_detected", anomalies.to_string()))
+        }
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(ack(&RatesyncAck::Error(err.to_string())))
+            .add_attribute("action", "receive_liquid_stake_rates")
+            .add_attribute("channel_id", channel_id)
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+/// The contract never sends packets, so there is nothing to acknowledge
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_ack"))
+}
+
+/// The contract never sends packets, so none can time out
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout"))
+}
+
+// Ensures the channel speaks the ratesync packet format on an allowed connection
+fn validate_channel(
+    deps: Deps,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != RATESYNC_ORDERING {
+        return Err(ContractError::InvalidIbcOrder {});
+    }
+
+    for version in [Some(channel.version.as_str()), counterparty_version]
+        .into_iter()
+        .flatten()
+    {
+        if version != RATESYNC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: version.to_string(),
+            });
+        }
+    }
+
+    ensure_connection_allowed(deps, &channel.connection_id)
+}
+
+fn ensure_connection_allowed(deps: Deps, connection_id: &str) -> Result<(), ContractError> {
+    if !ALLOWED_IBC_CONNECTIONS.has(deps.storage, connection_id) {
+        return Err(ContractError::IbcConnectionNotAllowed {
+            connection_id: connection_id.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+// Stores the rates of a packet, the connection being allowed stands in for the feeder role.
+// With a quorum config the channel submits the rates to their rounds as a feeder of its own.
+// Returns the hooks to send to the subscribers of the rates.
+fn receive_packet(
+    deps: DepsMut,
+    env: Env,
+    packet: IbcPacket,
+) -> Result<(LiquidStakeRatesResponse, Vec<SubMsg>), ContractError> {
+    let channel_id = packet.dest.channel_id;
+    let connection_id = IBC_CHANNELS
+        .may_load(deps.storage, &channel_id)?
+        .ok_or_else(|| ContractError::IbcChannelNotFound {
+            channel_id: channel_id.clone(),
+        })?;
+    ensure_connection_allowed(deps.as_ref(), &connection_id)?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    match from_json(&packet.data)? {
+        RatesyncPacket::LiquidStakeRates { rates } => {
+            let (results, hooks) = match QUORUM_CONFIG.may_load(deps.storage)? {
+                Some(quorum_config) => {
+                    // Channel IDs can't collide with the address of a feeder
+                    let feeder = Addr::unchecked(channel_id);
+                    submit_liquid_stake_rates(deps, &env, &config, &quorum_config, &feeder, rates)?
+                }
+                None => ingest_liquid_stake_rates(deps, &config, rates)?,
+            };
+            Ok((LiquidStakeRatesResponse { results }, hooks))
+        }
+    }
+}
+
+fn ack(ack: &RatesyncAck) -> Binary {
+    // Serializing the ack types can't fail
+    to_json_binary(ack).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_close_confirm, mock_ibc_channel_connect_ack,
+        mock_ibc_channel_open_init, mock_ibc_channel_open_try, mock_ibc_packet_recv, mock_info,
+        MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{attr, Decimal, Empty, IbcOrder, OwnedDeps};
+    use ratesync::lsr_helpers::denom_trace_to_hash;
+    use ratesync::lsr_msg::{
+        ExecuteMsg, InstantiateMsg, LiquidStakeRateEntry, LiquidStakeRateResult,
+    };
+    use ratesync::lsr_round::{QuorumConfig, RoundStatus, ROUNDS};
+    use ratesync::lsr_state::{Role, HISTORY_HEADS};
+
+    use super::*;
+    use crate::contract::{execute, instantiate};
+
+    const OWNER_ADDRESS: &str = "creator";
+    // Connection of the mocked IBC channels
+    const CONNECTION_ID: &str = "connection-2";
+    const CHANNEL_ID: &str = "channel-12";
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier, Empty> {
+        let mut deps = mock_dependencies();
+        let info = mock_info(OWNER_ADDRESS, &[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(OWNER_ADDRESS.to_string()),
+            transfer_channel_i_d: "channel-0".to_string(),
+            transfer_port_i_d: "transfer".to_string(),
+            deviation_count_limit: None,
+            deviation_threshold: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        deps
+    }
+
+    fn allow_connection(deps: DepsMut) {
+        let msg = ExecuteMsg::AllowIbcConnection {
+            connection_id: CONNECTION_ID.to_string(),
+        };
+        execute(deps, mock_env(), mock_info(OWNER_ADDRESS, &[]), msg).unwrap();
+    }
+
+    fn connect(mut deps: DepsMut) {
+        let msg = mock_ibc_channel_open_try(CHANNEL_ID, RATESYNC_ORDERING, RATESYNC_VERSION);
+        ibc_channel_open(deps.branch(), mock_env(), msg).unwrap();
+
+        let msg = mock_ibc_channel_connect_ack(CHANNEL_ID, RATESYNC_ORDERING, RATESYNC_VERSION);
+        ibc_channel_connect(deps, mock_env(), msg).unwrap();
+    }
+
+    fn rates_packet(rates: &[(&str, &str, u64)]) -> RatesyncPacket {
+        RatesyncPacket::LiquidStakeRates {
+            rates: rates
+                .iter()
+                .map(|(stk_denom, c_value, time)| LiquidStakeRateEntry {
+                    default_bond_denom: "somecoin".to_string(),
+                    stk_denom: stk_denom.to_string(),
+                    c_value: Decimal::from_str(c_value).unwrap(),
+                    controller_chain_time: *time,
+                    denom_trace: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn receive(deps: DepsMut, channel_id: &str, packet: &RatesyncPacket) -> RatesyncAck {
+        let msg = mock_ibc_packet_recv(channel_id, packet).unwrap();
+        let res = ibc_packet_receive(deps, mock_env(), msg).unwrap();
+        from_json(res.acknowledgement).unwrap()
+    }
+
+    #[test]
+    fn test_channel_handshake() {
+        let mut deps = setup();
+
+        // The connection must be allowed by the admin first
+        let msg = mock_ibc_channel_open_init(CHANNEL_ID, RATESYNC_ORDERING, RATESYNC_VERSION);
+        let res = ibc_channel_open(deps.as_mut(), mock_env(), msg.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::IbcConnectionNotAllowed {
+                connection_id: CONNECTION_ID.to_string()
+            })
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::AllowIbcConnection {
+                connection_id: CONNECTION_ID.to_string(),
+            },
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        allow_connection(deps.as_mut());
+
+        assert_eq!(ibc_channel_open(deps.as_mut(), mock_env(), msg), Ok(None));
+
+        let msg = mock_ibc_channel_open_init(CHANNEL_ID, IbcOrder::Ordered, RATESYNC_VERSION);
+        let res = ibc_channel_open(deps.as_mut(), mock_env(), msg);
+        assert_eq!(res, Err(ContractError::InvalidIbcOrder {}));
+
+        let msg = mock_ibc_channel_open_try(CHANNEL_ID, RATESYNC_ORDERING, "ratesync-2");
+        let res = ibc_channel_open(deps.as_mut(), mock_env(), msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidIbcVersion {
+                version: "ratesync-2".to_string()
+            })
+        );
+
+        let msg = mock_ibc_channel_connect_ack(CHANNEL_ID, RATESYNC_ORDERING, RATESYNC_VERSION);
+        let res = ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "ibc_channel_connect"),
+                attr("channel_id", CHANNEL_ID),
+                attr("connection_id", CONNECTION_ID),
+            ]
+        );
+        assert_eq!(
+            IBC_CHANNELS.load(&deps.storage, CHANNEL_ID).unwrap(),
+            CONNECTION_ID
+        );
+
+        let msg = mock_ibc_channel_close_confirm(CHANNEL_ID, RATESYNC_ORDERING, RATESYNC_VERSION);
+        ibc_channel_close(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(!IBC_CHANNELS.has(&deps.storage, CHANNEL_ID));
+    }
+
+    #[test]
+    fn test_packet_receive() {
+        let mut deps = setup();
+        allow_connection(deps.as_mut());
+        connect(deps.as_mut());
+
+        let stk_denom1 = "stk/somecoin1";
+        let stk_denom2 = "stk/somecoin2";
+        let ibc_hash_denom1 = denom_trace_to_hash(stk_denom1, "transfer", "channel-0").unwrap();
+        let ibc_hash_denom2 = denom_trace_to_hash(stk_denom2, "transfer", "channel-0").unwrap();
+
+        let packet = rates_packet(&[(stk_denom1, "1", 1), (stk_denom2, "1", 1)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert!(matches!(ack, RatesyncAck::Result(_)));
+
+        // The ack reports the anomaly result of every rate
+        let packet = rates_packet(&[(stk_denom1, "1", 2), (stk_denom2, "2", 2)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Result(LiquidStakeRatesResponse {
+                results: vec![
+                    LiquidStakeRateResult {
+                        stk_denom: stk_denom1.to_string(),
+                        stk_denom_ibc_hash: ibc_hash_denom1.clone(),
+                        anomaly_detected: false,
+                        quarantined: false,
+                        round_status: None,
+                    },
+                    LiquidStakeRateResult {
+                        stk_denom: stk_denom2.to_string(),
+                        stk_denom_ibc_hash: ibc_hash_denom2,
+                        anomaly_detected: true,
+                        quarantined: false,
+                        round_status: None,
+                    },
+                ]
+            })
+        );
+
+        let head = HISTORY_HEADS.load(&deps.storage, &ibc_hash_denom1).unwrap();
+        assert_eq!(head.len, 2);
+
+        // Packets on unknown channels are refused
+        let ack = receive(deps.as_mut(), "channel-99", &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Error(
+                ContractError::IbcChannelNotFound {
+                    channel_id: "channel-99".to_string()
+                }
+                .to_string()
+            )
+        );
+
+        // Packets of a disallowed connection are refused on open channels too
+        let msg = ExecuteMsg::DisallowIbcConnection {
+            connection_id: CONNECTION_ID.to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+
+        let packet = rates_packet(&[(stk_denom1, "1", 3)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Error(
+                ContractError::IbcConnectionNotAllowed {
+                    connection_id: CONNECTION_ID.to_string()
+                }
+                .to_string()
+            )
+        );
+
+        let head = HISTORY_HEADS.load(&deps.storage, &ibc_hash_denom1).unwrap();
+        assert_eq!(head.len, 2);
+    }
+
+    #[test]
+    fn test_packet_receive_invalid_batch() {
+        let mut deps = setup();
+        allow_connection(deps.as_mut());
+        connect(deps.as_mut());
+
+        // The whole packet is refused if a single rate is invalid
+        let packet = rates_packet(&[("stk/somecoin1", "1", 1), ("ibc/somecoin2", "1", 1)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert!(matches!(ack, RatesyncAck::Error(_)));
+
+        let ibc_hash_denom1 =
+            denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap();
+        assert!(!HISTORY_HEADS.has(&deps.storage, &ibc_hash_denom1));
+
+        let msg = mock_ibc_packet_recv(CHANNEL_ID, &"not a packet").unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: RatesyncAck = from_json(res.acknowledgement).unwrap();
+        assert!(matches!(ack, RatesyncAck::Error(_)));
+    }
+
+    #[test]
+    fn test_packet_receive_with_quorum() {
+        let mut deps = setup();
+        allow_connection(deps.as_mut());
+        connect(deps.as_mut());
+
+        let owner = mock_info(OWNER_ADDRESS, &[]);
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Feeder,
+            address: "feeder1".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: Some(QuorumConfig {
+                quorum: 2,
+                tolerance: Decimal::percent(1),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+        let stk_denom = "stk/somecoin1";
+        let ibc_hash_denom = denom_trace_to_hash(stk_denom, "transfer", "channel-0").unwrap();
+        let result = |round_status| LiquidStakeRateResult {
+            stk_denom: stk_denom.to_string(),
+            stk_denom_ibc_hash: ibc_hash_denom.clone(),
+            anomaly_detected: false,
+            quarantined: false,
+            round_status: Some(round_status),
+        };
+
+        // The channel submits the rates to their rounds instead of storing them
+        let packet = rates_packet(&[(stk_denom, "1", 1)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Result(LiquidStakeRatesResponse {
+                results: vec![result(RoundStatus::Buffered)]
+            })
+        );
+        assert!(!HISTORY_HEADS.has(&deps.storage, &ibc_hash_denom));
+
+        // A redelivered packet can't vote twice
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Error(
+                ContractError::DuplicateSubmission {
+                    denom: ibc_hash_denom.clone(),
+                    update_time: 1,
+                    feeder: CHANNEL_ID.to_string(),
+                }
+                .to_string()
+            )
+        );
+
+        // A feeder agreeing with the channel commits the rate
+        let msg = ExecuteMsg::LiquidStakeRate {
+            default_bond_denom: "somecoin".to_string(),
+            stk_denom: stk_denom.to_string(),
+            c_value: Decimal::one(),
+            controller_chain_time: 1,
+            denom_trace: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("feeder1", &[]), msg).unwrap();
+        assert!(res.attributes.contains(&attr("committed", "true")));
+        let head = HISTORY_HEADS.load(&deps.storage, &ibc_hash_denom).unwrap();
+        assert_eq!(head.len, 1);
+    }
+
+    #[test]
+    fn test_packet_receive_with_quorum_is_atomic() {
+        let mut deps = setup();
+        allow_connection(deps.as_mut());
+        connect(deps.as_mut());
+
+        let owner = mock_info(OWNER_ADDRESS, &[]);
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Feeder,
+            address: "feeder1".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: Some(QuorumConfig {
+                quorum: 2,
+                tolerance: Decimal::percent(1),
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+        let packet = rates_packet(&[("stk/somecoin1", "1", 1)]);
+        receive(deps.as_mut(), CHANNEL_ID, &packet);
+
+        // The second rate was already submitted by the channel, so the first one is not
+        // submitted either
+        let packet = rates_packet(&[("stk/somecoin2", "1", 1), ("stk/somecoin1", "1", 1)]);
+        let ack = receive(deps.as_mut(), CHANNEL_ID, &packet);
+        assert_eq!(
+            ack,
+            RatesyncAck::Error(
+                ContractError::DuplicateSubmission {
+                    denom: denom_trace_to_hash("stk/somecoin1", "transfer", "channel-0").unwrap(),
+                    update_time: 1,
+                    feeder: CHANNEL_ID.to_string(),
+                }
+                .to_string()
+            )
+        );
+        let ibc_hash_denom = denom_trace_to_hash("stk/somecoin2", "transfer", "channel-0").unwrap();
+        assert!(!ROUNDS.has(&deps.storage, (&ibc_hash_denom, 1)));
+    }
+}
@@ -1,3 +1,5 @@
 pub mod contract;
+pub mod ibc;
+pub mod migrations;
 
 pub use ratesync::lsr_error::ContractError;
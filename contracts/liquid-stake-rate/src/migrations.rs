@@ -0,0 +1,105 @@
+use cosmwasm_std::{Empty, Order, StdResult, Storage};
+use ratesync::{
+    lsr_error::ContractError,
+    lsr_state::{
+        AnomalyConfig, HistoryHead, RateEntry, Role, ThresholdMode, ANOMALY_CONFIG_BY_DENOM,
+        CONFIG, DEFAULT_ANOMALY_CONFIG, DENOM_METADATA, HISTORY_HEADS, LEGACY_LIQUID_STAKE_RATES,
+        REDEMPTION_RATES, ROLE_MEMBERS, STK_DENOMS_BY_BOND_DENOM,
+    },
+    migrations::MigrationStep,
+};
+
+/// Ordered list of state upgrade steps, oldest first
+pub const MIGRATIONS: &[MigrationStep<ContractError>] = &[
+    MigrationStep {
+        version: "1.1.0",
+        migrate: migrate_anomaly_configs,
+    },
+    MigrationStep {
+        version: "1.2.0",
+        migrate: grant_owner_feeder_role,
+    },
+    MigrationStep {
+        version: "1.3.0",
+        migrate: migrate_rate_histories,
+    },
+    MigrationStep {
+        version: "1.4.0",
+        migrate: index_bond_denoms,
+    },
+];
+
+/// Anomaly thresholds stored before 1.1.0 were compared in absolute units, so they keep
+/// the absolute mode instead of the new relative default. Those contracts also didn't
+/// store a default anomaly config and gave new denoms the built-in one.
+fn migrate_anomaly_configs(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let anomaly_configs = ANOMALY_CONFIG_BY_DENOM
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (denom, mut anomaly_config) in anomaly_configs {
+        anomaly_config.threshold_mode = ThresholdMode::Absolute;
+        ANOMALY_CONFIG_BY_DENOM.save(storage, &denom, &anomaly_config)?;
+    }
+
+    DEFAULT_ANOMALY_CONFIG.save(
+        storage,
+        &AnomalyConfig {
+            threshold_mode: ThresholdMode::Absolute,
+            ..AnomalyConfig::default()
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Before 1.2.0 only the owner could post rates, so it keeps doing so as a feeder
+fn grant_owner_feeder_role(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    ROLE_MEMBERS.save(storage, (Role::Feeder.as_str(), &config.owner), &Empty {})?;
+
+    Ok(())
+}
+
+/// Before 1.3.0 the history of a denom was stored as a single blob, which is split into
+/// one entry per rate
+fn migrate_rate_histories(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let histories = LEGACY_LIQUID_STAKE_RATES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (denom, history) in histories {
+        for rate in history.range(None, None, Order::Ascending) {
+            REDEMPTION_RATES.save(storage, (&denom, rate.update_time), &RateEntry::from(rate))?;
+        }
+        HISTORY_HEADS.save(
+            storage,
+            &denom,
+            &HistoryHead {
+                capacity: history.capacity(),
+                len: history.len() as u64,
+            },
+        )?;
+        LEGACY_LIQUID_STAKE_RATES.remove(storage, &denom);
+    }
+
+    Ok(())
+}
+
+/// Before 1.4.0 the stkTokens were not indexed by bond denom. Those tracked before their
+/// denoms were stored are indexed from the denoms given to the migrate message.
+fn index_bond_denoms(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let metadata = DENOM_METADATA
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (denom, metadata) in metadata {
+        STK_DENOMS_BY_BOND_DENOM.save(
+            storage,
+            (&metadata.default_bond_denom, &denom),
+            &Empty {},
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use cosmwasm_schema::write_api;
 
-use osmosis_pool_ratesync::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use osmosis_pool_ratesync::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
+        sudo: SudoMsg,
     }
 }
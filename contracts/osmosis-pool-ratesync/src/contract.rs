@@ -13,14 +13,20 @@ use osmosis_std::types::osmosis::{
 };
 
 use ratesync::{
-    lsr_helpers::{denom_trace_to_hash, validate_channel_id},
-    lsr_msg::{QueryMsg as LiquidStakeRateQueryMsg, RedemptionRateResponse},
+    lsr_msg::{PauseStatusResponse, QueryMsg as LiquidStakeRateQueryMsg, RedemptionRateResponse},
+    lsr_state::{DenomTrace, TransferHop},
+    migrations::migrate_contract,
+    ownership::{
+        accept_ownership, cancel_ownership, propose_ownership, query_pending_ownership,
+        PENDING_OWNERSHIP,
+    },
 };
 
 use crate::{
     error::ContractError,
     helpers::{convert_redemption_rate_to_scaling_factors, validate_pool_configuration},
-    msg::{ExecuteMsg, InstantiateMsg, Pools, QueryMsg},
+    migrations::MIGRATIONS,
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, Pools, QueryMsg, SudoMsg},
     state::{Config, Pool, CONFIG, POOLS},
 };
 
@@ -58,21 +64,28 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner_address,
             lsr_contract_address,
-        } => execute_update_config(deps, info, owner_address, lsr_contract_address),
+        } => execute_update_config(deps, info, lsr_contract_address),
+        ExecuteMsg::ProposeOwnership {
+            new_owner,
+            expires_at,
+        } => execute_propose_ownership(deps, env, info, new_owner, expires_at),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnership {} => execute_cancel_ownership(deps, info),
         ExecuteMsg::AddPool {
             pool_id,
             stk_token_denom,
             transfer_port_id,
             transfer_channel_id,
             asset_ordering,
+            denom_trace,
         } => {
             let pool = Pool {
                 pool_id,
                 stk_token_denom: stk_token_denom.clone(),
                 transfer_port_id: transfer_port_id.clone(),
                 transfer_channel_id: transfer_channel_id.clone(),
+                denom_trace,
                 ibc_hash_stk_denom: "".to_string(),
                 asset_ordering: asset_ordering.clone(),
                 last_updated: 0,
@@ -84,13 +97,13 @@ pub fn execute(
         ExecuteMsg::UpdateScalingFactor { pool_id } => {
             execute_update_scaling_factor(deps, env, pool_id)
         }
+        ExecuteMsg::RateUpdated { denom, .. } => execute_rate_updated(deps, env, info, denom),
     }
 }
 
 pub fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner_address: String,
     lsr_contract_address: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
@@ -100,18 +113,73 @@ pub fn execute_update_config(
     }
 
     let updated_config = Config {
-        owner_address: deps.api.addr_validate(&owner_address)?,
         lsr_contract_address: deps.api.addr_validate(&lsr_contract_address)?,
+        ..config
     };
 
     CONFIG.save(deps.storage, &updated_config)?;
 
     Ok(Response::new()
         .add_attribute("action", "update_config")
-        .add_attribute("owner_address", owner_address)
         .add_attribute("lsr_contract_address", lsr_contract_address))
 }
 
+pub fn execute_propose_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let pending_ownership = propose_ownership(
+        deps.storage,
+        deps.api,
+        &env.block,
+        &config.owner_address,
+        &info.sender,
+        &new_owner,
+        expires_at,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_ownership")
+        .add_attribute("new_owner", pending_ownership.new_owner)
+        .add_attribute(
+            "expires_at",
+            expires_at.map_or("none".to_string(), |time| time.to_string()),
+        ))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let previous_owner = config.owner_address;
+    config.owner_address = accept_ownership(deps.storage, &env.block, &info.sender)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner_address", config.owner_address))
+}
+
+pub fn execute_cancel_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    cancel_ownership(deps.storage, &config.owner_address, &info.sender)?;
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership"))
+}
+
 pub fn execute_add_pool(
     deps: DepsMut,
     env: Env,
@@ -152,16 +220,28 @@ pub fn execute_add_pool(
         });
     }
 
-    let transfer_channel_id = pool.transfer_channel_id.clone();
     let asset_ordering = pool.asset_ordering.clone();
 
-    validate_channel_id(&transfer_channel_id.clone())?;
+    // Build the stkToken denom trace, the first hop is always the pool's transfer channel
+    let first_hop = TransferHop {
+        port_id: pool.transfer_port_id.clone(),
+        channel_id: pool.transfer_channel_id.clone(),
+    };
+    let denom_trace = match pool.denom_trace.clone() {
+        Some(trace) => {
+            if trace.base_denom != pool.stk_token_denom || trace.path.first() != Some(&first_hop) {
+                return Err(ContractError::DenomTraceMismatch {});
+            }
+            trace
+        }
+        None => DenomTrace {
+            path: vec![first_hop],
+            base_denom: pool.stk_token_denom.clone(),
+        },
+    };
+    denom_trace.validate()?;
 
-    let ibc_hash_stk_denom = denom_trace_to_hash(
-        &pool.stk_token_denom,
-        &pool.transfer_port_id,
-        &transfer_channel_id,
-    )?;
+    let ibc_hash_stk_denom = denom_trace.ibc_hash()?;
 
     validate_pool_configuration(
         stableswap_pool,
@@ -191,14 +271,20 @@ pub fn execute_remove_pool(
         ContractError::Unauthorized {}
     );
 
+    remove_pool(deps, pool_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_pool")
+        .add_attribute("pool_id", pool_id.to_string()))
+}
+
+fn remove_pool(deps: DepsMut, pool_id: u64) -> Result<(), ContractError> {
     if !POOLS.has(deps.storage, pool_id) {
         return Err(ContractError::PoolNotFound { pool_id });
     }
     POOLS.remove(deps.storage, pool_id);
 
-    Ok(Response::new()
-        .add_attribute("action", "remove_pool")
-        .add_attribute("pool_id", pool_id.to_string()))
+    Ok(())
 }
 
 pub fn execute_update_scaling_factor(
@@ -213,6 +299,28 @@ pub fn execute_update_scaling_factor(
 
     let lsr_contract_address = &CONFIG.load(deps.storage)?.lsr_contract_address;
 
+    // Check the pause status first so a paused rate is told apart from a failing query
+    let pause_status_query_msg = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: lsr_contract_address.to_string(),
+        msg: to_json_binary(&LiquidStakeRateQueryMsg::PauseStatus {
+            denom: Some(pool.ibc_hash_stk_denom.clone()),
+        })?,
+    });
+
+    let pause_status_response: PauseStatusResponse = deps
+        .querier
+        .query(&pause_status_query_msg)
+        .map_err(|err| ContractError::UnableToQueryPauseStatus {
+            stk_denom: pool.ibc_hash_stk_denom.clone(),
+            error: err.to_string(),
+        })?;
+
+    if pause_status_response.serving_paused {
+        return Err(ContractError::Paused {
+            stk_denom: pool.ibc_hash_stk_denom,
+        });
+    }
+
     let redemption_rate_query_msg = QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: lsr_contract_address.to_string(),
         msg: to_json_binary(&LiquidStakeRateQueryMsg::RedemptionRate {
@@ -254,10 +362,88 @@ pub fn execute_update_scaling_factor(
         .add_message(adjust_factors_msg))
 }
 
+// Updates the scaling factors of the pools of a stkToken when the lsr contract accepts a
+// new rate. The rate is queried again, so that the lsr pause flags are respected.
+pub fn execute_rate_updated(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure!(
+        info.sender == config.lsr_contract_address,
+        ContractError::Unauthorized {}
+    );
+
+    let pool_ids = POOLS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((pool_id, pool)) if pool.ibc_hash_stk_denom == denom => Some(Ok(pool_id)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut response = Response::new();
+    for pool_id in &pool_ids {
+        let pool_response = execute_update_scaling_factor(deps.branch(), env.clone(), *pool_id)?;
+        response = response.add_submessages(pool_response.messages);
+    }
+
+    let pool_ids: Vec<String> = pool_ids.iter().map(u64::to_string).collect();
+    Ok(response
+        .add_attribute("action", "rate_updated")
+        .add_attribute("denom", denom)
+        .add_attribute("pool_ids", pool_ids.join(",")))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetOwner { owner_address } => sudo_set_owner(deps, owner_address),
+        SudoMsg::RemovePool { pool_id } => sudo_remove_pool(deps, pool_id),
+    }
+}
+
+pub fn sudo_set_owner(deps: DepsMut, owner_address: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let previous_owner = config.owner_address;
+    config.owner_address = deps.api.addr_validate(&owner_address)?;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNERSHIP.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_owner")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner_address", config.owner_address))
+}
+
+pub fn sudo_remove_pool(deps: DepsMut, pool_id: u64) -> Result<Response, ContractError> {
+    remove_pool(deps, pool_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_remove_pool")
+        .add_attribute("pool_id", pool_id.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let info = migrate_contract(deps.storage, CONTRACT_NAME, CONTRACT_VERSION, MIGRATIONS)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", info.from_version)
+        .add_attribute("to_version", info.to_version)
+        .add_attribute("applied_steps", info.applied_steps.join(",")))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::PendingOwnership {} => to_json_binary(&query_pending_ownership(deps.storage)?),
         QueryMsg::Pool { pool_id } => to_json_binary(&POOLS.load(deps.storage, pool_id)?),
         QueryMsg::AllPools {} => to_json_binary(&query_all_pools(deps)?),
     }
@@ -274,8 +460,9 @@ pub fn query_all_pools(deps: Deps) -> StdResult<Pools> {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::str::FromStr;
     use std::vec;
 
@@ -284,8 +471,8 @@ mod tests {
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
     use cosmwasm_std::{
         attr, from_json, to_json_binary, Addr, CosmosMsg, Decimal, Empty, Env, MessageInfo,
-        OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Timestamp,
-        WasmQuery,
+        OwnedDeps, Querier, QuerierResult, QueryRequest, Storage, SystemError, SystemResult,
+        Timestamp, WasmQuery,
     };
     use osmosis_std::types::cosmos::base::v1beta1::Coin;
     use osmosis_std::types::osmosis::gamm::poolmodels::stableswap::v1beta1::{
@@ -293,9 +480,13 @@ mod tests {
     };
     use osmosis_std::types::osmosis::poolmanager::v1beta1::PoolRequest;
     use prost::Message;
+    use ratesync::lsr_helpers::denom_trace_to_hash;
+    use ratesync::lsr_state::PauseFlags;
+    use ratesync::migrations::MigrationError;
+    use ratesync::ownership::{OwnershipError, PendingOwnership, PendingOwnershipResponse};
     use serde::{Deserialize, Serialize};
 
-    use crate::contract::{execute, instantiate, query};
+    use crate::contract::{execute, instantiate, migrate, query};
     use crate::state::{AssetOrdering, Config, Pool};
     use crate::ContractError;
 
@@ -307,6 +498,7 @@ mod tests {
     pub struct WasmMockQuerier {
         base_querier: MockQuerier<Empty>,
         lsr_redemption_rates: HashMap<String, RedemptionRateResponse>,
+        lsr_paused_denoms: HashSet<String>,
         pools: HashMap<u64, PoolQueryResponse>,
     }
 
@@ -337,6 +529,7 @@ mod tests {
             WasmMockQuerier {
                 base_querier: MockQuerier::new(&[]),
                 lsr_redemption_rates: HashMap::new(),
+                lsr_paused_denoms: HashSet::new(),
                 pools: HashMap::new(),
             }
         }
@@ -354,6 +547,21 @@ mod tests {
                                     None => SystemResult::Err(SystemError::Unknown {}),
                                 }
                             }
+                            LiquidStakeRateQueryMsg::PauseStatus { denom } => {
+                                let paused = denom
+                                    .as_ref()
+                                    .is_some_and(|denom| self.lsr_paused_denoms.contains(denom));
+                                let resp = PauseStatusResponse {
+                                    global: PauseFlags::default(),
+                                    denom: denom.map(|_| PauseFlags {
+                                        ingestion: false,
+                                        serving: paused,
+                                    }),
+                                    ingestion_paused: false,
+                                    serving_paused: paused,
+                                };
+                                SystemResult::Ok(to_json_binary(&resp).into())
+                            }
                             _ => panic!("Mocked query not supported for LSR contract"),
                         }
                     } else {
@@ -383,10 +591,16 @@ mod tests {
                 RedemptionRateResponse {
                     redemption_rate: c_value,
                     update_time: 1,
+                    stale: false,
                 },
             );
         }
 
+        // Pauses serving of the redemption rate of the specified denom
+        pub fn mock_lsr_pause(&mut self, denom: String) {
+            self.lsr_paused_denoms.insert(denom);
+        }
+
         // Adds a mocked entry to the querier such that queries with the specified pool ID
         // return a stableswap pool with specified liquidity
         pub fn mock_stableswap_pool(&mut self, pool_id: u64, pool: &Pool) {
@@ -479,6 +693,7 @@ mod tests {
             stk_token_denom: stk_token_denom.to_string(),
             transfer_port_id: transfer_port_id.to_string(),
             transfer_channel_id: transfer_channel_id.to_string(),
+            denom_trace: None,
             ibc_hash_stk_denom: ibc_hash_stk_denom.to_string(),
             asset_ordering,
             last_updated: 0,
@@ -493,6 +708,7 @@ mod tests {
             transfer_port_id: "transfer".to_string(),
             transfer_channel_id: "channel-0".to_string(),
             asset_ordering: pool.asset_ordering,
+            denom_trace: pool.denom_trace,
         }
     }
 
@@ -517,12 +733,10 @@ mod tests {
     fn test_update_config() {
         let (mut deps, env, info) = default_instantiate();
 
-        // Update the admin and oracle addresses
-        let updated_admin = "update_admin";
+        // Update the oracle address
         let updated_lsr_contract = "updated_lsr_contract";
 
         let update_msg = ExecuteMsg::UpdateConfig {
-            owner_address: updated_admin.to_string(),
             lsr_contract_address: updated_lsr_contract.to_string(),
         };
         let resp = execute(deps.as_mut(), env.clone(), info, update_msg).unwrap();
@@ -530,7 +744,6 @@ mod tests {
             resp.attributes,
             vec![
                 attr("action", "update_config"),
-                attr("owner_address", updated_admin.to_string()),
                 attr("lsr_contract_address", updated_lsr_contract.to_string()),
             ]
         );
@@ -542,12 +755,264 @@ mod tests {
         assert_eq!(
             updated_config,
             Config {
-                owner_address: Addr::unchecked(updated_admin.to_string()),
+                owner_address: Addr::unchecked(ADMIN_ADDRESS.to_string()),
                 lsr_contract_address: Addr::unchecked(updated_lsr_contract.to_string())
             }
         )
     }
 
+    #[test]
+    fn test_transfer_ownership() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let updated_admin = "update_admin";
+
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: updated_admin.to_string(),
+            expires_at: None,
+        };
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("not_admin", &[]),
+            propose_msg.clone(),
+        );
+        assert_eq!(
+            resp,
+            Err(ContractError::Ownership(OwnershipError::NotOwner {}))
+        );
+
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), propose_msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "propose_ownership"),
+                attr("new_owner", updated_admin),
+                attr("expires_at", "none"),
+            ]
+        );
+
+        let query_resp = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwnership {}).unwrap();
+        let pending: PendingOwnershipResponse = from_json(query_resp).unwrap();
+        assert_eq!(
+            pending.pending_ownership,
+            Some(PendingOwnership {
+                new_owner: Addr::unchecked(updated_admin),
+                expires_at: None,
+            })
+        );
+
+        // The current owner stays in charge until the transfer is accepted
+        let accept_msg = ExecuteMsg::AcceptOwnership {};
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), accept_msg.clone());
+        assert_eq!(
+            resp,
+            Err(ContractError::Ownership(OwnershipError::NotPendingOwner {}))
+        );
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(updated_admin, &[]),
+            accept_msg,
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "accept_ownership"),
+                attr("previous_owner", ADMIN_ADDRESS),
+                attr("owner_address", updated_admin),
+            ]
+        );
+
+        let query_resp = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let config: Config = from_json(query_resp).unwrap();
+        assert_eq!(config.owner_address, Addr::unchecked(updated_admin));
+
+        // The previous owner lost access
+        let remove_msg = ExecuteMsg::RemovePool { pool_id: 1 };
+        let resp = execute(deps.as_mut(), env, info, remove_msg);
+        assert_eq!(resp, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_cancel_ownership_transfer() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: "update_admin".to_string(),
+            expires_at: Some(env.block.time.seconds() + 100),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), propose_msg).unwrap();
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::CancelOwnership {},
+        )
+        .unwrap();
+        assert_eq!(resp.attributes, vec![attr("action", "cancel_ownership")]);
+
+        let resp = execute(
+            deps.as_mut(),
+            env,
+            mock_info("update_admin", &[]),
+            ExecuteMsg::AcceptOwnership {},
+        );
+        assert_eq!(
+            resp,
+            Err(ContractError::Ownership(
+                OwnershipError::NoPendingOwnership {}
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rate_updated() {
+        let (mut deps, env, info) = default_instantiate();
+
+        // Two pools of the same stkToken and a pool of another one
+        let pools = [
+            get_test_pool(
+                1,
+                "stk/uosmo",
+                "transfer",
+                "channel-0",
+                AssetOrdering::StkTokenFirst,
+            ),
+            get_test_pool(
+                2,
+                "stk/uosmo",
+                "transfer",
+                "channel-0",
+                AssetOrdering::NativeTokenFirst,
+            ),
+            get_test_pool(
+                3,
+                "stk/uatom",
+                "transfer",
+                "channel-0",
+                AssetOrdering::StkTokenFirst,
+            ),
+        ];
+        let denom = pools[0].ibc_hash_stk_denom.clone();
+        deps.querier
+            .mock_lsr_redemption_rate(denom.clone(), Decimal::from_str("1.2").unwrap());
+        for pool in pools {
+            deps.querier.mock_stableswap_pool(pool.pool_id, &pool);
+            let add_msg = get_add_pool_msg(pool.pool_id, pool);
+            execute(deps.as_mut(), env.clone(), info.clone(), add_msg).unwrap();
+        }
+
+        let hook_msg = ExecuteMsg::RateUpdated {
+            denom: denom.clone(),
+            redemption_rate: Decimal::from_str("1.2").unwrap(),
+            update_time: 1,
+        };
+
+        // Only the lsr contract sends hooks
+        let resp = execute(deps.as_mut(), env.clone(), info, hook_msg.clone());
+        assert_eq!(resp, Err(ContractError::Unauthorized {}));
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(LSR_CONTRACT_ADDRESS, &[]),
+            hook_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "rate_updated"),
+                attr("denom", denom.clone()),
+                attr("pool_ids", "1,2"),
+            ]
+        );
+
+        let expected_msgs: Vec<CosmosMsg> = [(1, vec![100000, 120000]), (2, vec![120000, 100000])]
+            .into_iter()
+            .map(|(pool_id, scaling_factors)| {
+                MsgStableSwapAdjustScalingFactors {
+                    sender: env.contract.address.to_string(),
+                    pool_id,
+                    scaling_factors,
+                }
+                .into()
+            })
+            .collect();
+        let msgs: Vec<CosmosMsg> = resp.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(msgs, expected_msgs);
+
+        // The hook fails while serving is paused, which the lsr contract records
+        deps.querier.mock_lsr_pause(denom.clone());
+        let resp = execute(
+            deps.as_mut(),
+            env,
+            mock_info(LSR_CONTRACT_ADDRESS, &[]),
+            hook_msg,
+        );
+        assert_eq!(resp, Err(ContractError::Paused { stk_denom: denom }));
+    }
+
+    #[test]
+    fn test_sudo() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let pool = get_test_pool(
+            1,
+            "stkA",
+            "transfer",
+            "channel-0",
+            AssetOrdering::StkTokenFirst,
+        );
+        deps.querier.mock_stableswap_pool(1, &pool);
+        let add_msg = get_add_pool_msg(1, pool);
+        execute(deps.as_mut(), env.clone(), info.clone(), add_msg).unwrap();
+
+        let propose_msg = ExecuteMsg::ProposeOwnership {
+            new_owner: "update_admin".to_string(),
+            expires_at: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), propose_msg).unwrap();
+
+        // Governance replaces the owner and drops the pending proposal
+        let sudo_msg = SudoMsg::SetOwner {
+            owner_address: "governance_admin".to_string(),
+        };
+        let resp = sudo(deps.as_mut(), env.clone(), sudo_msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "sudo_set_owner"),
+                attr("previous_owner", info.sender.as_str()),
+                attr("owner_address", "governance_admin"),
+            ]
+        );
+
+        let query_resp = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let config: Config = from_json(query_resp).unwrap();
+        assert_eq!(config.owner_address, Addr::unchecked("governance_admin"));
+
+        let query_resp = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwnership {}).unwrap();
+        let pending: PendingOwnershipResponse = from_json(query_resp).unwrap();
+        assert_eq!(pending.pending_ownership, None);
+
+        // Pools can be removed without the owner
+        let sudo_msg = SudoMsg::RemovePool { pool_id: 1 };
+        let resp = sudo(deps.as_mut(), env.clone(), sudo_msg.clone()).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "sudo_remove_pool"), attr("pool_id", "1")]
+        );
+        assert!(!POOLS.has(&deps.storage, 1));
+
+        let resp = sudo(deps.as_mut(), env, sudo_msg);
+        assert_eq!(resp, Err(ContractError::PoolNotFound { pool_id: 1 }));
+    }
+
     #[test]
     fn test_add_remove_pools() {
         let (mut deps, env, info) = default_instantiate();
@@ -645,6 +1110,7 @@ mod tests {
             transfer_port_id: "".to_string(),
             transfer_channel_id: "".to_string(),
             asset_ordering: AssetOrdering::StkTokenFirst,
+            denom_trace: None,
         };
         let add_duplicate_pool_resp = execute(deps.as_mut(), env, info, add_duplicate_pool_msg);
         assert_eq!(
@@ -766,6 +1232,84 @@ mod tests {
         assert_eq!(add_resp2, Err(ContractError::InvalidPoolAssetOrdering {}));
     }
 
+    #[test]
+    fn test_add_multi_hop_pool() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let denom_trace = DenomTrace {
+            path: vec![
+                TransferHop {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-0".to_string(),
+                },
+                TransferHop {
+                    port_id: "transfer".to_string(),
+                    channel_id: "channel-5".to_string(),
+                },
+            ],
+            base_denom: "stkuatom".to_string(),
+        };
+        let pool = Pool {
+            denom_trace: Some(denom_trace.clone()),
+            ibc_hash_stk_denom:
+                "ibc/CA93CB3C97FF3FCBBD38C0CDB63776FA7E5FBB2B5CEB8EAC473AB9B07DC39CF2".to_string(),
+            ..get_test_pool(
+                1,
+                "stkuatom",
+                "transfer",
+                "channel-0",
+                AssetOrdering::StkTokenFirst,
+            )
+        };
+        deps.querier.mock_stableswap_pool(1, &pool);
+
+        let add_msg = get_add_pool_msg(1, pool.clone());
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), add_msg).unwrap();
+        assert_eq!(
+            resp.attributes[2],
+            attr("pool_stk_token_denom", pool.ibc_hash_stk_denom.clone())
+        );
+
+        let query_resp = query(deps.as_ref(), env.clone(), QueryMsg::Pool { pool_id: 1 }).unwrap();
+        let pool_resp: Pool = from_json(query_resp).unwrap();
+        assert_eq!(pool_resp, pool);
+
+        deps.querier.mock_stableswap_pool(2, &pool);
+
+        // The first hop of the trace must be the pool's transfer channel
+        let mut mismatched_trace = denom_trace.clone();
+        mismatched_trace.path[0].channel_id = "channel-1".to_string();
+        let add_msg = get_add_pool_msg(
+            2,
+            Pool {
+                pool_id: 2,
+                denom_trace: Some(mismatched_trace),
+                ..pool.clone()
+            },
+        );
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), add_msg);
+        assert_eq!(resp, Err(ContractError::DenomTraceMismatch {}));
+
+        // Every hop is validated
+        let mut invalid_trace = denom_trace;
+        invalid_trace.path[1].channel_id = "chan-5".to_string();
+        let add_msg = get_add_pool_msg(
+            2,
+            Pool {
+                pool_id: 2,
+                denom_trace: Some(invalid_trace),
+                ..pool
+            },
+        );
+        let resp = execute(deps.as_mut(), env, info, add_msg);
+        assert_eq!(
+            resp,
+            Err(ContractError::LsrError(
+                "Invalid channelID: chan-5".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn test_unauthorized() {
         let (mut deps, env, _) = default_instantiate();
@@ -861,4 +1405,152 @@ mod tests {
             Err(ContractError::PoolNotFound { pool_id: 1 })
         );
     }
+
+    #[test]
+    fn test_update_scaling_factor_paused() {
+        let pool_id = 2;
+        let pool = get_test_pool(
+            pool_id,
+            "stk/uosmo",
+            "transfer",
+            "channel-0",
+            AssetOrdering::StkTokenFirst,
+        );
+        let ibc_hash_stk_denom = pool.ibc_hash_stk_denom.clone();
+
+        let (mut deps, env, info) = default_instantiate();
+        deps.querier.mock_lsr_redemption_rate(
+            ibc_hash_stk_denom.clone(),
+            Decimal::from_str("1.2").unwrap(),
+        );
+        deps.querier.mock_stableswap_pool(pool_id, &pool);
+
+        let add_pool_msg = get_add_pool_msg(pool_id, pool);
+        execute(deps.as_mut(), env.clone(), info.clone(), add_pool_msg).unwrap();
+
+        // The rate is still served, but the contract must not apply it while paused
+        deps.querier.mock_lsr_pause(ibc_hash_stk_denom.clone());
+
+        let update_msg = ExecuteMsg::UpdateScalingFactor { pool_id };
+        let resp = execute(deps.as_mut(), env.clone(), info, update_msg);
+        assert_eq!(
+            resp,
+            Err(ContractError::Paused {
+                stk_denom: ibc_hash_stk_denom
+            })
+        );
+
+        let query_pool_resp = query(deps.as_ref(), env, QueryMsg::Pool { pool_id }).unwrap();
+        let queried_pool: Pool = from_json(query_pool_resp).unwrap();
+        assert_eq!(queried_pool.last_updated, 0);
+    }
+
+    // Raw state written by the 1.0.0 contract, used to check that migrations keep it readable
+    const FIXTURE_CONFIG: &str = r#"{"owner_address":"admin","lsr_contract_address":"lsr"}"#;
+    const FIXTURE_POOL: &str = r#"{"pool_id":1,"stk_token_denom":"stkA","transfer_port_id":"transfer","transfer_channel_id":"channel-0","ibc_hash_stk_denom":"ibc/BFBA68CE35F0F89E4735FCF05EE369A4FADC3C6B465F62A91E1444C79271FB38","asset_ordering":"stk_token_first","last_updated":1000}"#;
+
+    // Helper function to write the 1.0.0 state fixtures into storage
+    fn fixture_deps(
+        contract_name: &str,
+        version: &str,
+    ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, Empty> {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: WasmMockQuerier::new(),
+            custom_query_type: Default::default(),
+        };
+
+        cw2::set_contract_version(deps.as_mut().storage, contract_name, version).unwrap();
+        deps.storage
+            .set(CONFIG.as_slice(), FIXTURE_CONFIG.as_bytes());
+        deps.storage.set(&POOLS.key(1), FIXTURE_POOL.as_bytes());
+
+        deps
+    }
+
+    #[test]
+    fn test_migrate_from_v1_0_0() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "1.0.0");
+        let env = mock_env();
+
+        let resp = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(resp.attributes[0], attr("action", "migrate"));
+        assert_eq!(resp.attributes[1], attr("from_version", "1.0.0"));
+        assert_eq!(resp.attributes[2], attr("to_version", CONTRACT_VERSION));
+        assert_eq!(resp.attributes[3], attr("applied_steps", ""));
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Config is still readable
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let config: Config = from_json(resp).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                owner_address: Addr::unchecked(ADMIN_ADDRESS),
+                lsr_contract_address: Addr::unchecked(LSR_CONTRACT_ADDRESS),
+            }
+        );
+
+        // Pools are still readable
+        let expected_pool = Pool {
+            last_updated: 1000,
+            ..get_test_pool(
+                1,
+                "stkA",
+                "transfer",
+                "channel-0",
+                AssetOrdering::StkTokenFirst,
+            )
+        };
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::AllPools {}).unwrap();
+        let pools: Pools = from_json(resp).unwrap();
+        assert_eq!(
+            pools,
+            Pools {
+                pools: vec![expected_pool.clone()]
+            }
+        );
+
+        // The migrated pool can still be updated
+        deps.querier.mock_lsr_redemption_rate(
+            expected_pool.ibc_hash_stk_denom,
+            Decimal::from_str("1.1").unwrap(),
+        );
+        let update_msg = ExecuteMsg::UpdateScalingFactor { pool_id: 1 };
+        let info = mock_info(ADMIN_ADDRESS, &[]);
+        execute(deps.as_mut(), env, info, update_msg).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_refuses_downgrade() {
+        let mut deps = fixture_deps(CONTRACT_NAME, "99.0.0");
+
+        let resp = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        assert_eq!(
+            resp,
+            Err(ContractError::Migration(MigrationError::CannotDowngrade {
+                stored: "99.0.0".to_string(),
+                current: CONTRACT_VERSION.to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_migrate_refuses_other_contract() {
+        let mut deps = fixture_deps("crates.io:liquid-stake-rate", "1.0.0");
+
+        let resp = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        assert_eq!(
+            resp,
+            Err(ContractError::Migration(
+                MigrationError::InvalidContractName {
+                    expected: CONTRACT_NAME.to_string(),
+                    actual: "crates.io:liquid-stake-rate".to_string(),
+                }
+            ))
+        );
+    }
 }
@@ -1,5 +1,7 @@
 use cosmwasm_std::StdError;
 use ratesync::lsr_error::ContractError as LsrContractError;
+use ratesync::migrations::MigrationError;
+use ratesync::ownership::OwnershipError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -13,6 +15,12 @@ pub enum ContractError {
     #[error("Unable to query redemption rate of {stk_denom} from lsr contract, {error}")]
     UnableToQueryRedemptionRate { stk_denom: String, error: String },
 
+    #[error("Unable to query pause status of {stk_denom} from lsr contract, {error}")]
+    UnableToQueryPauseStatus { stk_denom: String, error: String },
+
+    #[error("Serving of redemption rates for {stk_denom} is paused in the lsr contract")]
+    Paused { stk_denom: String },
+
     #[error("Pool {pool_id} is not configured in the contract")]
     PoolNotFound { pool_id: u64 },
 
@@ -25,6 +33,9 @@ pub enum ContractError {
     #[error("Invalid denom: {denom}")]
     InvalidDenom { denom: String },
 
+    #[error("The denom trace does not match the pool's stkToken denom and transfer channel")]
+    DenomTraceMismatch {},
+
     #[error("The specified asset ordering does not match the underlying pool")]
     InvalidPoolAssetOrdering {},
 
@@ -36,6 +47,12 @@ pub enum ContractError {
 
     #[error("LSR error: {0}")]
     LsrError(String),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
 }
 
 impl From<LsrContractError> for ContractError {
@@ -0,0 +1,6 @@
+use ratesync::migrations::MigrationStep;
+
+use crate::ContractError;
+
+/// Ordered list of state upgrade steps, oldest first
+pub const MIGRATIONS: &[MigrationStep<ContractError>] = &[];
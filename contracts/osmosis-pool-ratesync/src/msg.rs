@@ -1,5 +1,7 @@
 use crate::state::Pool;
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal;
+use ratesync::lsr_state::DenomTrace;
 
 use crate::state::AssetOrdering;
 
@@ -13,9 +15,19 @@ pub struct InstantiateMsg {
 #[cw_serde]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner_address: String,
         lsr_contract_address: String,
     },
+    /// Propose a new owner, who has to accept before the transfer takes effect
+    ProposeOwnership {
+        /// Proposed owner address
+        new_owner: String,
+        /// Unix timestamp in seconds after which the proposal can no longer be accepted
+        expires_at: Option<u64>,
+    },
+    /// Accept the pending ownership transfer as the proposed owner
+    AcceptOwnership {},
+    /// Cancel the pending ownership transfer
+    CancelOwnership {},
     AddPool {
         /// Pool ID of the Osmosis pool
         pool_id: u64,
@@ -27,6 +39,9 @@ pub enum ExecuteMsg {
         transfer_channel_id: String,
         /// The ordering of the stkToken vs nativeToken assets in the Osmosis pool,
         asset_ordering: AssetOrdering,
+        /// The full IBC trace of stkTokens routed through intermediate chains,
+        /// its first hop and base denom must match the fields above
+        denom_trace: Option<DenomTrace>,
     },
     RemovePool {
         pool_id: u64,
@@ -35,8 +50,32 @@ pub enum ExecuteMsg {
     UpdateScalingFactor {
         pool_id: u64,
     },
+
+    /// Hook sent by the lsr contract when it accepts a new rate, updates the scaling factors
+    /// of every pool of the stkToken
+    RateUpdated {
+        /// The ibc hash of the stkToken as it lives on Osmosis
+        denom: String,
+        /// The c-value of the stkToken
+        redemption_rate: Decimal,
+        /// Unix timestamp of the rate
+        update_time: u64,
+    },
 }
 
+/// Overrides for chain governance, e.g. to recover a deployment whose owner key was lost
+#[cw_serde]
+pub enum SudoMsg {
+    /// Replace the owner, dropping any pending ownership transfer
+    SetOwner { owner_address: String },
+    /// Stop adjusting the scaling factors of a pool
+    RemovePool { pool_id: u64 },
+}
+
+/// Migrates the contract state to the current contract version
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -44,6 +83,10 @@ pub enum QueryMsg {
     #[returns(crate::state::Config)]
     Config {},
 
+    /// Returns the pending ownership transfer
+    #[returns(ratesync::ownership::PendingOwnershipResponse)]
+    PendingOwnership {},
+
     /// Returns a the configuration for a specific stkToken stableswap pool
     #[returns(crate::state::Pool)]
     Pool { pool_id: u64 },
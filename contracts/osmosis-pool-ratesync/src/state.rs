@@ -1,6 +1,7 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
 use cw_storage_plus::{Item, Map};
+use ratesync::lsr_state::DenomTrace;
 use std::fmt;
 
 // The config defines the admin and oracle contract addresses
@@ -23,6 +24,8 @@ pub struct Pool {
     pub transfer_port_id: String,
     /// The transfer channel id
     pub transfer_channel_id: String,
+    /// The full IBC trace of the stkToken, if it was routed through intermediate chains
+    pub denom_trace: Option<DenomTrace>,
     /// The ibc hash of stkToken
     pub ibc_hash_stk_denom: String,
     /// The ordering of the stkToken vs nativeToken assets in the Osmosis pool,
@@ -1,4 +1,10 @@
+pub mod lsr_anomaly;
 pub mod lsr_error;
 pub mod lsr_helpers;
+pub mod lsr_history;
+pub mod lsr_ibc;
 pub mod lsr_msg;
+pub mod lsr_round;
 pub mod lsr_state;
+pub mod migrations;
+pub mod ownership;
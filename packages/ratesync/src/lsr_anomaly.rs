@@ -0,0 +1,280 @@
+use cosmwasm_std::Decimal;
+
+use crate::lsr_state::ThresholdMode;
+
+/// Decides whether a new redemption rate deviates too far from the previous rates
+pub trait AnomalyDetector {
+    /// Returns true if `redemption_rate` is anomalous compared to `baseline`,
+    /// the previous rates ordered newest first. An empty baseline is never anomalous.
+    fn is_anomalous(&self, baseline: &[Decimal], redemption_rate: Decimal) -> bool;
+}
+
+/// Flags rates that deviate from the arithmetic mean by more than the threshold
+pub struct MeanDeviation {
+    pub threshold: Decimal,
+    pub mode: ThresholdMode,
+}
+
+impl AnomalyDetector for MeanDeviation {
+    fn is_anomalous(&self, baseline: &[Decimal], redemption_rate: Decimal) -> bool {
+        match mean(baseline) {
+            Some(mean) => exceeds_threshold(mean, redemption_rate, self.threshold, self.mode),
+            None => false,
+        }
+    }
+}
+
+/// Flags rates that deviate from the median by more than the threshold
+pub struct MedianDeviation {
+    pub threshold: Decimal,
+    pub mode: ThresholdMode,
+}
+
+impl AnomalyDetector for MedianDeviation {
+    fn is_anomalous(&self, baseline: &[Decimal], redemption_rate: Decimal) -> bool {
+        match median(baseline) {
+            Some(median) => exceeds_threshold(median, redemption_rate, self.threshold, self.mode),
+            None => false,
+        }
+    }
+}
+
+/// Flags rates that deviate from the exponential moving average by more than the threshold.
+/// The smoothing factor is 2 / (n + 1) for a baseline of n rates.
+pub struct EmaDeviation {
+    pub threshold: Decimal,
+    pub mode: ThresholdMode,
+}
+
+impl AnomalyDetector for EmaDeviation {
+    fn is_anomalous(&self, baseline: &[Decimal], redemption_rate: Decimal) -> bool {
+        let Some((oldest, newer)) = baseline.split_last() else {
+            return false;
+        };
+
+        let alpha = Decimal::from_ratio(2u64, baseline.len() as u64 + 1);
+        let ema = newer.iter().rev().fold(*oldest, |ema, rate| {
+            *rate * alpha + ema * (Decimal::one() - alpha)
+        });
+
+        exceeds_threshold(ema, redemption_rate, self.threshold, self.mode)
+    }
+}
+
+/// Flags rates more than `max_z_score` standard deviations away from the mean.
+/// Needs at least two rates to judge, and flags any deviation if they are all equal.
+pub struct ZScore {
+    pub max_z_score: Decimal,
+}
+
+impl AnomalyDetector for ZScore {
+    fn is_anomalous(&self, baseline: &[Decimal], redemption_rate: Decimal) -> bool {
+        let Some(mean) = mean(baseline).filter(|_| baseline.len() >= 2) else {
+            return false;
+        };
+
+        let variance = baseline
+            .iter()
+            .map(|rate| {
+                let diff = rate.abs_diff(mean);
+                diff * diff
+            })
+            .sum::<Decimal>()
+            / Decimal::from_ratio(baseline.len() as u64, 1u64);
+        let std_dev = variance.sqrt();
+        let deviation = redemption_rate.abs_diff(mean);
+
+        if std_dev.is_zero() {
+            return !deviation.is_zero();
+        }
+
+        deviation / std_dev > self.max_z_score
+    }
+}
+
+fn mean(rates: &[Decimal]) -> Option<Decimal> {
+    if rates.is_empty() {
+        return None;
+    }
+
+    Some(rates.iter().sum::<Decimal>() / Decimal::from_ratio(rates.len() as u64, 1u64))
+}
+
+/// Returns the middle rate, or the mean of the two middle rates of an even count
+pub fn median(rates: &[Decimal]) -> Option<Decimal> {
+    if rates.is_empty() {
+        return None;
+    }
+
+    let mut sorted = rates.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[middle - 1] + sorted[middle]) / Decimal::from_ratio(2u64, 1u64))
+    } else {
+        Some(sorted[middle])
+    }
+}
+
+fn exceeds_threshold(
+    reference: Decimal,
+    redemption_rate: Decimal,
+    threshold: Decimal,
+    mode: ThresholdMode,
+) -> bool {
+    if reference.is_zero() {
+        return false;
+    }
+
+    let deviation = reference.abs_diff(redemption_rate);
+    match mode {
+        ThresholdMode::Absolute => deviation > threshold,
+        ThresholdMode::Relative => deviation / reference > threshold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::Decimal;
+
+    use super::{AnomalyDetector, EmaDeviation, MeanDeviation, MedianDeviation, ZScore};
+    use crate::lsr_state::ThresholdMode;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn rates(values: &[&str]) -> Vec<Decimal> {
+        values.iter().map(|value| dec(value)).collect()
+    }
+
+    #[test]
+    fn test_empty_baseline() {
+        let detectors: [&dyn AnomalyDetector; 4] = [
+            &MeanDeviation {
+                threshold: dec("0.05"),
+                mode: ThresholdMode::Absolute,
+            },
+            &MedianDeviation {
+                threshold: dec("0.05"),
+                mode: ThresholdMode::Absolute,
+            },
+            &EmaDeviation {
+                threshold: dec("0.05"),
+                mode: ThresholdMode::Absolute,
+            },
+            &ZScore {
+                max_z_score: dec("3"),
+            },
+        ];
+
+        for detector in detectors {
+            assert!(!detector.is_anomalous(&[], dec("100")));
+        }
+    }
+
+    #[test]
+    fn test_mean_deviation() {
+        let detector = MeanDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Absolute,
+        };
+        // mean is 1.1
+        let baseline = rates(&["1.2", "1.1", "1.0"]);
+
+        assert!(!detector.is_anomalous(&baseline, dec("1.15")));
+        assert!(!detector.is_anomalous(&baseline, dec("1.05")));
+        assert!(detector.is_anomalous(&baseline, dec("1.16")));
+        assert!(detector.is_anomalous(&baseline, dec("1.04")));
+    }
+
+    #[test]
+    fn test_median_deviation() {
+        let detector = MedianDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Absolute,
+        };
+
+        // An outlier moves the mean but not the median
+        let baseline = rates(&["1.0", "5.0", "1.01"]);
+        assert!(!detector.is_anomalous(&baseline, dec("1.03")));
+        assert!(detector.is_anomalous(&baseline, dec("1.07")));
+
+        // median of an even count is the mean of the middle rates
+        let baseline = rates(&["1.3", "1.0", "1.2", "1.1"]);
+        assert!(!detector.is_anomalous(&baseline, dec("1.2")));
+        assert!(detector.is_anomalous(&baseline, dec("1.21")));
+    }
+
+    #[test]
+    fn test_ema_deviation() {
+        let detector = EmaDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Absolute,
+        };
+        // alpha is 0.5: 1.0 -> 1.1 -> 1.25
+        let baseline = rates(&["1.4", "1.2", "1.0"]);
+
+        assert!(!detector.is_anomalous(&baseline, dec("1.3")));
+        assert!(!detector.is_anomalous(&baseline, dec("1.2")));
+        assert!(detector.is_anomalous(&baseline, dec("1.31")));
+        // the mean of 1.2 would accept this rate
+        assert!(detector.is_anomalous(&baseline, dec("1.15")));
+    }
+
+    #[test]
+    fn test_relative_threshold() {
+        let detector = MeanDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Relative,
+        };
+
+        // 5% of a baseline of 2.0 is 0.1
+        let baseline = rates(&["2.0"]);
+        assert!(!detector.is_anomalous(&baseline, dec("2.1")));
+        assert!(!detector.is_anomalous(&baseline, dec("1.9")));
+        assert!(detector.is_anomalous(&baseline, dec("2.11")));
+        assert!(detector.is_anomalous(&baseline, dec("1.89")));
+
+        // 5% of a baseline of 0.5 is 0.025
+        let baseline = rates(&["0.5"]);
+        assert!(!detector.is_anomalous(&baseline, dec("0.525")));
+        assert!(detector.is_anomalous(&baseline, dec("0.53")));
+
+        let detector = MedianDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Relative,
+        };
+        assert!(detector.is_anomalous(&baseline, dec("0.53")));
+
+        let detector = EmaDeviation {
+            threshold: dec("0.05"),
+            mode: ThresholdMode::Relative,
+        };
+        assert!(detector.is_anomalous(&baseline, dec("0.53")));
+    }
+
+    #[test]
+    fn test_z_score() {
+        let detector = ZScore {
+            max_z_score: dec("2"),
+        };
+        // mean is 1.1 and standard deviation is 0.1
+        let baseline = rates(&["1.2", "1.0"]);
+
+        assert!(!detector.is_anomalous(&baseline, dec("1.3")));
+        assert!(!detector.is_anomalous(&baseline, dec("0.9")));
+        assert!(detector.is_anomalous(&baseline, dec("1.31")));
+        assert!(detector.is_anomalous(&baseline, dec("0.89")));
+
+        // a single rate has no spread to compare against
+        assert!(!detector.is_anomalous(&rates(&["1.0"]), dec("2.0")));
+
+        // equal rates flag any deviation
+        let baseline = rates(&["1.0", "1.0"]);
+        assert!(!detector.is_anomalous(&baseline, dec("1.0")));
+        assert!(detector.is_anomalous(&baseline, dec("1.0001")));
+    }
+}
@@ -1,6 +1,8 @@
 use cosmwasm_std::{Decimal, StdError};
 use thiserror::Error;
 
+use crate::{migrations::MigrationError, ownership::OwnershipError};
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -12,6 +14,12 @@ pub enum ContractError {
     #[error("Invalid channelID: {channel_id}")]
     InvalidChannelID { channel_id: String },
 
+    #[error("Invalid portID: {port_id}")]
+    InvalidPortID { port_id: String },
+
+    #[error("Invalid denom trace: {reason}")]
+    InvalidDenomTrace { reason: String },
+
     #[error("Invalid denom: {reason}")]
     InvalidDenom { reason: String },
 
@@ -26,6 +34,98 @@ pub enum ContractError {
 
     #[error("The denom for the redemption rate metric must not be an IBC denom, {denom} provided")]
     InvalidRedemptionRateDenom { denom: String },
+
+    #[error("Redemption rate {value} for {denom} rejected as anomalous")]
+    AnomalousRedemptionRate { denom: String, value: Decimal },
+
+    #[error("Batch contains no redemption rates")]
+    EmptyRateBatch {},
+
+    #[error("Batch contains more than one redemption rate for {denom}")]
+    DuplicateRateBatchEntry { denom: String },
+
+    #[error("No redemption rate for {denom} at {update_time}")]
+    RedemptionRateNotFound { denom: String, update_time: u64 },
+
+    #[error("No quarantined redemption rate for {denom} at {update_time}")]
+    QuarantinedRateNotFound { denom: String, update_time: u64 },
+
+    #[error("Invalid history capacity: {capacity}")]
+    InvalidHistoryCapacity { capacity: u64 },
+
+    #[error("Deviation count limit {count_limit} exceeds the history capacity {capacity}")]
+    CountLimitExceedsCapacity { count_limit: u64, capacity: u64 },
+
+    #[error("Invalid max age: {max_age_seconds}")]
+    InvalidMaxAge { max_age_seconds: u64 },
+
+    #[error("Redemption rate for {denom} updated at {update_time} is older than {max_age_seconds} seconds")]
+    StaleRedemptionRate {
+        denom: String,
+        update_time: u64,
+        max_age_seconds: u64,
+    },
+
+    #[error("History of {denom} does not cover the TWAP window of {window_seconds} seconds, oldest rate is from {oldest_update_time}")]
+    TwapWindowNotCovered {
+        denom: String,
+        window_seconds: u64,
+        oldest_update_time: u64,
+    },
+
+    #[error("Redemption rate {operation} for {denom} is paused")]
+    Paused { denom: String, operation: String },
+
+    #[error("IBC connection {connection_id} is not allowed")]
+    IbcConnectionNotAllowed { connection_id: String },
+
+    #[error("Only unordered IBC channels are supported")]
+    InvalidIbcOrder {},
+
+    #[error("Invalid IBC channel version: {version}")]
+    InvalidIbcVersion { version: String },
+
+    #[error("IBC channel {channel_id} is not a ratesync channel")]
+    IbcChannelNotFound { channel_id: String },
+
+    #[error("Time {time} is outside the history of {denom}, which covers {oldest_update_time} to {latest_update_time}")]
+    TimeOutsideHistory {
+        denom: String,
+        time: u64,
+        oldest_update_time: u64,
+        latest_update_time: u64,
+    },
+
+    #[error("No transfer path registered for {stk_denom}")]
+    TransferPathNotFound { stk_denom: String },
+
+    #[error("No redemption rates or anomaly config registered for {denom}")]
+    DenomNotFound { denom: String },
+
+    #[error("Denom {denom} was retired at {retired_at}")]
+    DenomRetired { denom: String, retired_at: u64 },
+
+    #[error("{address} is not subscribed to {denom}")]
+    SubscriberNotFound { denom: String, address: String },
+
+    #[error("Invalid quorum config: {reason}")]
+    InvalidQuorumConfig { reason: String },
+
+    #[error("Quorum of {quorum} is more than the {feeders} feeders")]
+    QuorumUnreachable { quorum: u64, feeders: u64 },
+
+    #[error("{feeder} already submitted a rate for {denom} at {update_time}")]
+    DuplicateSubmission {
+        denom: String,
+        update_time: u64,
+        feeder: String,
+    },
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    Ownership(#[from] OwnershipError),
 }
 
 impl From<ContractError> for StdError {
@@ -1,14 +1,21 @@
-use std::str::FromStr;
-
-use cosmwasm_std::{Addr, Api, Decimal, Deps, StdResult};
+use cosmwasm_std::{from_json, Addr, Api, Binary, Decimal, Order, StdError, StdResult, Storage};
 use sha2::{Digest, Sha256};
 
 use crate::{
     lsr_error::ContractError,
-    lsr_state::{RedemptionRate, ANOMALY_CONFIG_BY_DENOM, LIQUID_STAKE_RATES},
+    lsr_history::range_rates,
+    lsr_msg::RedemptionRateParams,
+    lsr_state::{
+        AnomalyConfig, Config, DenomTrace, HistoryHead, PauseFlags, RedemptionRate, Role,
+        TransferHop, TransferPath, DEFAULT_ANOMALY_CONFIG, GLOBAL_PAUSE, HISTORY_HEADS,
+        HISTORY_ITEM_CAP, MAX_AGE_BY_DENOM, PAUSE_BY_DENOM, RETIRED_DENOMS, ROLE_MEMBERS,
+        TRANSFER_PATH_BY_DENOM,
+    },
 };
 
 const CHANNEL_ID_PERFIX: &str = "channel";
+const PORT_ID_MIN_LENGTH: usize = 2;
+const PORT_ID_MAX_LENGTH: usize = 128;
 
 /// This helper function is to validate an optional string passed for address
 pub fn option_string_to_addr(
@@ -50,39 +57,172 @@ pub fn validate_native_denom(denom: &str) -> Result<(), ContractError> {
     Ok(())
 }
 
-pub fn validate_redemption_rate(
-    deps: Deps,
+/// Compares a new redemption rate against the history of its denom
+pub fn detect_anomaly(
+    storage: &dyn Storage,
+    anomaly_config: &AnomalyConfig,
+    denom: &str,
     redemption_rate: Decimal,
-    denom: String,
-) -> Result<bool, ContractError> {
-    let anomaly_config = ANOMALY_CONFIG_BY_DENOM.load(deps.storage, &denom)?;
+) -> StdResult<bool> {
+    let mut baseline = Vec::with_capacity(anomaly_config.count_limit as usize);
+    for rr in range_rates(storage, denom, None, None, Order::Descending) {
+        if baseline.len() == anomaly_config.count_limit as usize {
+            break;
+        }
+
+        // Quarantined rates were never served, so they are left out of the baseline
+        let rr = rr?;
+        if rr.quarantined || (anomaly_config.exclude_flagged && rr.anomaly_detected) {
+            continue;
+        }
+        baseline.push(rr.redemption_rate);
+    }
+
+    Ok(anomaly_config
+        .strategy
+        .detector(anomaly_config.threshold, anomaly_config.threshold_mode)
+        .is_anomalous(&baseline, redemption_rate))
+}
+
+/// Ensures the sender is the admin
+pub fn ensure_admin(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Ensures the sender was granted the role
+pub fn ensure_role(storage: &dyn Storage, role: Role, sender: &Addr) -> Result<(), ContractError> {
+    if !ROLE_MEMBERS.has(storage, (role.as_str(), sender)) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+/// Fails with DenomRetired if the denom was removed with a tombstone
+pub fn ensure_not_retired(storage: &dyn Storage, denom: &str) -> Result<(), ContractError> {
+    if let Some(tombstone) = RETIRED_DENOMS.may_load(storage, denom)? {
+        return Err(ContractError::DenomRetired {
+            denom: denom.to_string(),
+            retired_at: tombstone.retired_at,
+        });
+    }
+
+    Ok(())
+}
+
+/// Loads the history head of a denom, failing with DenomRetired instead of not found
+/// if the denom was removed with a tombstone
+pub fn load_history_head(storage: &dyn Storage, denom: &str) -> Result<HistoryHead, ContractError> {
+    ensure_not_retired(storage, denom)?;
+
+    Ok(HISTORY_HEADS.load(storage, denom)?)
+}
+
+/// Returns the pause flags in effect for a denom, combining the global and denom flags
+pub fn get_pause_flags(storage: &dyn Storage, denom: &str) -> StdResult<PauseFlags> {
+    let global = GLOBAL_PAUSE.may_load(storage)?.unwrap_or_default();
+    let by_denom = PAUSE_BY_DENOM.may_load(storage, denom)?.unwrap_or_default();
+
+    Ok(global.or(by_denom))
+}
+
+/// Updates the pause flags of a denom, or the global flags if no denom is given.
+/// Flags that are None keep their value.
+pub fn update_pause_flags(
+    storage: &mut dyn Storage,
+    denom: Option<&str>,
+    ingestion: Option<bool>,
+    serving: Option<bool>,
+) -> StdResult<PauseFlags> {
+    let current = match denom {
+        Some(denom) => PAUSE_BY_DENOM.may_load(storage, denom)?,
+        None => GLOBAL_PAUSE.may_load(storage)?,
+    }
+    .unwrap_or_default();
+
+    let flags = PauseFlags {
+        ingestion: ingestion.unwrap_or(current.ingestion),
+        serving: serving.unwrap_or(current.serving),
+    };
 
-    let c_value_rates = LIQUID_STAKE_RATES
-        .load(deps.storage, &denom)?
-        .get_latest_range(anomaly_config.count_limit as usize);
+    match denom {
+        Some(denom) if flags == PauseFlags::default() => PAUSE_BY_DENOM.remove(storage, denom),
+        Some(denom) => PAUSE_BY_DENOM.save(storage, denom, &flags)?,
+        None => GLOBAL_PAUSE.save(storage, &flags)?,
+    }
+
+    Ok(flags)
+}
 
-    let moving_average = calculate_average_redemption_rate(c_value_rates)?;
-    let deviation = moving_average.abs_diff(redemption_rate);
+/// Returns true if a rate updated at `update_time` is older than the denom's max age
+pub fn is_stale(storage: &dyn Storage, denom: &str, update_time: u64, now: u64) -> StdResult<bool> {
+    let max_age_seconds = MAX_AGE_BY_DENOM.may_load(storage, denom)?;
+
+    Ok(max_age_seconds
+        .is_some_and(|max_age_seconds| now.saturating_sub(update_time) > max_age_seconds))
+}
+
+/// Returns the anomaly config for newly seen denoms
+pub fn get_default_anomaly_config(storage: &dyn Storage) -> StdResult<AnomalyConfig> {
+    Ok(DEFAULT_ANOMALY_CONFIG
+        .may_load(storage)?
+        .unwrap_or_default())
+}
+
+/// Returns the number of rates kept in the history of a denom
+pub fn get_history_capacity(storage: &dyn Storage, denom: &str) -> StdResult<u64> {
+    Ok(HISTORY_HEADS
+        .may_load(storage, denom)?
+        .map_or(HISTORY_ITEM_CAP, |head| head.capacity))
+}
 
-    if moving_average != Decimal::zero() && deviation > anomaly_config.threshold {
-        return Ok(true);
+/// Ensures the moving average window of the anomaly detection fits in the history
+pub fn validate_count_limit(count_limit: u64, capacity: u64) -> Result<(), ContractError> {
+    if count_limit > capacity {
+        return Err(ContractError::CountLimitExceedsCapacity {
+            count_limit,
+            capacity,
+        });
     }
 
-    Ok(false)
+    Ok(())
 }
 
-fn calculate_average_redemption_rate(
-    redemption_rates: Vec<RedemptionRate>,
-) -> Result<Decimal, ContractError> {
-    let count = Decimal::from_str(&redemption_rates.len().to_string())?;
+/// Calculates the time-weighted average of the rates over `[window_start, window_end]`,
+/// where each rate holds from its update time until the next rate's update time.
+/// Rates must be ordered newest first. Returns None if the oldest rate was updated
+/// after `window_start`.
+pub fn calculate_twap(
+    redemption_rates: &[RedemptionRate],
+    window_start: u64,
+    window_end: u64,
+) -> StdResult<Option<Decimal>> {
+    let window = window_end.saturating_sub(window_start);
+    if window == 0 {
+        return Err(StdError::generic_err(
+            "twap window must be greater than zero",
+        ));
+    }
+
+    let mut weighted_sum = Decimal::zero();
+    let mut period_end = window_end;
 
-    if count.is_zero() {
-        Ok(Decimal::zero())
-    } else {
-        let total_redemption_rate: Decimal =
-            redemption_rates.iter().map(|rr| rr.redemption_rate).sum();
-        Ok(total_redemption_rate / count)
+    for rr in redemption_rates {
+        let period_start = rr.update_time.clamp(window_start, window_end);
+        let duration = Decimal::from_ratio(period_end.saturating_sub(period_start), 1u64);
+        weighted_sum = weighted_sum.checked_add(rr.redemption_rate.checked_mul(duration)?)?;
+
+        if rr.update_time <= window_start {
+            return Ok(Some(weighted_sum / Decimal::from_ratio(window, 1u64)));
+        }
+        period_end = period_start;
     }
+
+    Ok(None)
 }
 
 // Validates that the channel ID is of the form `channel-N`
@@ -108,41 +248,109 @@ pub fn validate_channel_id(channel_id: &str) -> Result<(), ContractError> {
     Ok(())
 }
 
+// Validates that the port ID is a valid ICS-24 identifier
+pub fn validate_port_id(port_id: &str) -> Result<(), ContractError> {
+    let valid_length = (PORT_ID_MIN_LENGTH..=PORT_ID_MAX_LENGTH).contains(&port_id.len());
+    let set = ['.', '_', '+', '-', '#', '[', ']', '<', '>'];
+    let valid_chars = port_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || set.contains(&c));
+
+    if !valid_length || !valid_chars {
+        return Err(ContractError::InvalidPortID {
+            port_id: port_id.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the transfer path of an stkToken and whether it was registered for the denom,
+/// falling back to the path from the config
+pub fn get_transfer_path(
+    storage: &dyn Storage,
+    config: &Config,
+    stk_denom: &str,
+) -> StdResult<(TransferPath, bool)> {
+    match TRANSFER_PATH_BY_DENOM.may_load(storage, stk_denom)? {
+        Some(path) => Ok((path, true)),
+        None => Ok((
+            TransferPath {
+                transfer_port_i_d: config.transfer_port_i_d.clone(),
+                transfer_channel_i_d: config.transfer_channel_i_d.clone(),
+                intermediate_hops: vec![],
+            },
+            false,
+        )),
+    }
+}
+
+/// Returns the IBC denom hash of an stkToken on the oracle chain using its transfer path
+pub fn stk_denom_to_hash(
+    storage: &dyn Storage,
+    config: &Config,
+    stk_denom: &str,
+) -> Result<String, ContractError> {
+    let (path, _) = get_transfer_path(storage, config, stk_denom)?;
+
+    path.to_denom_trace(stk_denom).ibc_hash()
+}
+
 // Given a base denom and channelID, returns the IBC denom hash
 // E.g. base_denom: uosmo, channel_id: channel-0 => ibc/{hash(transfer/channel-0/uosmo)}
-// Note: This function only supports ibc denom's that originated on the controller chain
+// Note: This function only supports a single hop, use DenomTrace for multi-hop denoms
 pub fn denom_trace_to_hash(
     base_denom: &str,
     transfer_port_id: &str,
     channel_id: &str,
 ) -> Result<String, ContractError> {
-    if base_denom.starts_with("ibc/") {
-        return Err(ContractError::InvalidRedemptionRateDenom {
-            denom: base_denom.to_string(),
-        });
+    DenomTrace {
+        path: vec![TransferHop {
+            port_id: transfer_port_id.to_string(),
+            channel_id: channel_id.to_string(),
+        }],
+        base_denom: base_denom.to_string(),
     }
+    .ibc_hash()
+}
 
-    let denom_trace = format!("{transfer_port_id}/{channel_id}/{base_denom}");
-
+// Given a full denom trace path, returns the IBC denom hash
+// E.g. transfer/channel-0/transfer/channel-5/uosmo => ibc/{hash(transfer/channel-0/transfer/channel-5/uosmo)}
+pub fn hash_denom_trace_path(denom_trace_path: &str) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(denom_trace.as_bytes());
+    hasher.update(denom_trace_path.as_bytes());
     let result = hasher.finalize();
     let hash = hex::encode(result);
 
-    let ibc_hash = format!("ibc/{}", hash.to_uppercase());
-    Ok(ibc_hash)
+    format!("ibc/{}", hash.to_uppercase())
+}
+
+/// Parses the optional params of the RedemptionRate query
+pub fn parse_redemption_rate_params(
+    params: Option<Binary>,
+) -> Result<RedemptionRateParams, ContractError> {
+    match params {
+        Some(params) => from_json(params).map_err(|err| ContractError::InvalidQueryRequest {
+            reason: format!("invalid params: {err}"),
+        }),
+        None => Ok(RedemptionRateParams::default()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use cosmwasm_std::testing::mock_dependencies;
-    use cosmwasm_std::Addr;
+    use cosmwasm_std::{Addr, Decimal};
 
     use crate::{
         lsr_error::ContractError,
         lsr_helpers::{
-            denom_trace_to_hash, option_string_to_addr, validate_channel_id, validate_native_denom,
+            calculate_twap, denom_trace_to_hash, option_string_to_addr, validate_channel_id,
+            validate_native_denom, validate_port_id,
         },
+        lsr_state::{DenomTrace, RedemptionRate, TransferHop},
     };
 
     #[test]
@@ -256,4 +464,153 @@ mod tests {
             Ok("ibc/A4DB47A9D3CF9A068D454513891B526702455D3EF08FB9EB558C561F9DC2B701".to_string()),
         );
     }
+
+    fn get_test_trace(hops: &[(&str, &str)], base_denom: &str) -> DenomTrace {
+        DenomTrace {
+            path: hops
+                .iter()
+                .map(|(port_id, channel_id)| TransferHop {
+                    port_id: port_id.to_string(),
+                    channel_id: channel_id.to_string(),
+                })
+                .collect(),
+            base_denom: base_denom.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_port_id() {
+        assert_eq!(validate_port_id("transfer"), Ok(()));
+        assert_eq!(validate_port_id("wasm.osmo1abc_def-1"), Ok(()));
+
+        for port_id in ["", "t", "trans/fer", "trans fer"] {
+            assert_eq!(
+                validate_port_id(port_id),
+                Err(ContractError::InvalidPortID {
+                    port_id: port_id.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_hop_denom_trace() {
+        let trace = get_test_trace(
+            &[("transfer", "channel-0"), ("transfer", "channel-5")],
+            "stkuatom",
+        );
+        assert_eq!(trace.validate(), Ok(()));
+        assert_eq!(
+            trace.full_path(),
+            "transfer/channel-0/transfer/channel-5/stkuatom"
+        );
+        assert_eq!(
+            trace.ibc_hash(),
+            Ok("ibc/CA93CB3C97FF3FCBBD38C0CDB63776FA7E5FBB2B5CEB8EAC473AB9B07DC39CF2".to_string()),
+        );
+
+        // A single hop trace hashes like denom_trace_to_hash
+        let trace = get_test_trace(&[("transfer", "channel-0")], "stkuatom");
+        assert_eq!(
+            trace.ibc_hash(),
+            denom_trace_to_hash("stkuatom", "transfer", "channel-0")
+        );
+        assert_eq!(
+            trace.ibc_hash(),
+            Ok("ibc/84D8DBB86B0CF82D9BA1B1FDF7F097BC4C3A7D1647D1D9E2AE643586FCCD92E7".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_invalid_denom_trace() {
+        let trace = get_test_trace(&[], "stkuatom");
+        assert_eq!(
+            trace.validate(),
+            Err(ContractError::InvalidDenomTrace {
+                reason: "path must contain at least one hop".to_string()
+            })
+        );
+
+        let trace = get_test_trace(&[("transfer", "channel-0")], "");
+        assert_eq!(
+            trace.validate(),
+            Err(ContractError::InvalidDenomTrace {
+                reason: "base denom must not be empty".to_string()
+            })
+        );
+
+        let trace = get_test_trace(
+            &[("transfer", "channel-0"), ("transfer", "chan-5")],
+            "stkuatom",
+        );
+        assert_eq!(
+            trace.validate(),
+            Err(ContractError::InvalidChannelID {
+                channel_id: "chan-5".to_string()
+            })
+        );
+
+        let trace = get_test_trace(&[("transfer", "channel-0"), ("", "channel-5")], "stkuatom");
+        assert_eq!(
+            trace.validate(),
+            Err(ContractError::InvalidPortID {
+                port_id: "".to_string()
+            })
+        );
+
+        let trace = get_test_trace(&[("transfer", "channel-0")], "ibc/ABC");
+        assert_eq!(
+            trace.ibc_hash(),
+            Err(ContractError::InvalidRedemptionRateDenom {
+                denom: "ibc/ABC".to_string()
+            })
+        );
+    }
+
+    fn get_test_rates(rates: &[(&str, u64)]) -> Vec<RedemptionRate> {
+        rates
+            .iter()
+            .map(|(redemption_rate, update_time)| RedemptionRate {
+                denom: "ibc/denom".to_string(),
+                redemption_rate: Decimal::from_str(redemption_rate).unwrap(),
+                update_time: *update_time,
+                anomaly_detected: false,
+                quarantined: false,
+                overridden: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calculate_twap() {
+        let rates = get_test_rates(&[("1.2", 100), ("1.1", 50), ("1.0", 0)]);
+
+        // 1.2 * 40 + 1.1 * 50 + 1.0 * 10
+        assert_eq!(
+            calculate_twap(&rates, 40, 140).unwrap(),
+            Some(Decimal::from_str("1.13").unwrap())
+        );
+        // The window starts exactly at a rate update
+        assert_eq!(
+            calculate_twap(&rates, 50, 150).unwrap(),
+            Some(Decimal::from_str("1.15").unwrap())
+        );
+        // The window ends before the latest rate
+        assert_eq!(
+            calculate_twap(&rates, 0, 100).unwrap(),
+            Some(Decimal::from_str("1.05").unwrap())
+        );
+        // The window is covered by the latest rate
+        assert_eq!(
+            calculate_twap(&rates, 120, 140).unwrap(),
+            Some(Decimal::from_str("1.2").unwrap())
+        );
+
+        // The history must cover the start of the window
+        let rates = get_test_rates(&[("1.2", 100), ("1.1", 50)]);
+        assert_eq!(calculate_twap(&rates, 40, 140).unwrap(), None);
+        assert_eq!(calculate_twap(&[], 40, 140).unwrap(), None);
+
+        assert!(calculate_twap(&rates, 140, 140).is_err());
+    }
 }
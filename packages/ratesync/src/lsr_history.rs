@@ -0,0 +1,284 @@
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::lsr_state::{HistoryHead, RateEntry, RedemptionRate, HISTORY_HEADS, REDEMPTION_RATES};
+
+/// Adds a rate to the history of its denom, replacing the rate stored at the same time.
+/// The oldest rates are dropped once the history exceeds its capacity.
+pub fn add_rate(storage: &mut dyn Storage, rate: &RedemptionRate) -> StdResult<()> {
+    let denom = rate.denom.as_str();
+    let mut head = HISTORY_HEADS.may_load(storage, denom)?.unwrap_or_default();
+
+    if !REDEMPTION_RATES.has(storage, (denom, rate.update_time)) {
+        head.len += 1;
+    }
+    REDEMPTION_RATES.save(storage, (denom, rate.update_time), &RateEntry::from(rate))?;
+
+    truncate_history(storage, denom, &mut head)?;
+    HISTORY_HEADS.save(storage, denom, &head)
+}
+
+/// Changes the capacity of the history of a denom, dropping the oldest rates that no
+/// longer fit
+pub fn set_history_capacity(
+    storage: &mut dyn Storage,
+    denom: &str,
+    capacity: u64,
+) -> StdResult<()> {
+    let mut head = HISTORY_HEADS.may_load(storage, denom)?.unwrap_or_default();
+    head.capacity = capacity;
+
+    truncate_history(storage, denom, &mut head)?;
+    HISTORY_HEADS.save(storage, denom, &head)
+}
+
+// Removes the oldest rates beyond the capacity of the history
+fn truncate_history(
+    storage: &mut dyn Storage,
+    denom: &str,
+    head: &mut HistoryHead,
+) -> StdResult<()> {
+    if head.len <= head.capacity {
+        return Ok(());
+    }
+
+    let oldest_update_times = REDEMPTION_RATES
+        .prefix(denom)
+        .keys(storage, None, None, Order::Ascending)
+        .take((head.len - head.capacity) as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+    for update_time in oldest_update_times {
+        REDEMPTION_RATES.remove(storage, (denom, update_time));
+    }
+    head.len = head.capacity;
+
+    Ok(())
+}
+
+/// Removes the rate of a denom stored at `update_time`, returning it if there was one
+pub fn remove_rate(
+    storage: &mut dyn Storage,
+    denom: &str,
+    update_time: u64,
+) -> StdResult<Option<RedemptionRate>> {
+    let Some(entry) = REDEMPTION_RATES.may_load(storage, (denom, update_time))? else {
+        return Ok(None);
+    };
+
+    REDEMPTION_RATES.remove(storage, (denom, update_time));
+    let mut head = HISTORY_HEADS.load(storage, denom)?;
+    head.len = head.len.saturating_sub(1);
+    HISTORY_HEADS.save(storage, denom, &head)?;
+
+    Ok(Some(entry.into_rate(denom)))
+}
+
+/// Removes every rate of a denom along with its history head
+pub fn clear_history(storage: &mut dyn Storage, denom: &str) -> StdResult<()> {
+    let update_times = REDEMPTION_RATES
+        .prefix(denom)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for update_time in update_times {
+        REDEMPTION_RATES.remove(storage, (denom, update_time));
+    }
+    HISTORY_HEADS.remove(storage, denom);
+
+    Ok(())
+}
+
+/// Returns the rates of a denom with a time after `start_after` and before `end_before`,
+/// both bounds being exclusive
+pub fn range_rates<'a>(
+    storage: &'a dyn Storage,
+    denom: &str,
+    start_after: Option<u64>,
+    end_before: Option<u64>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<RedemptionRate>> + 'a> {
+    let denom = denom.to_string();
+    Box::new(
+        REDEMPTION_RATES
+            .prefix(&denom)
+            .range(
+                storage,
+                start_after.map(Bound::exclusive),
+                end_before.map(Bound::exclusive),
+                order,
+            )
+            .map(move |item| item.map(|(_, entry)| entry.into_rate(&denom))),
+    )
+}
+
+/// Returns the first rate of a denom matching the predicate, walking the history in
+/// the given order
+pub fn find_rate<P: Fn(&RedemptionRate) -> bool>(
+    storage: &dyn Storage,
+    denom: &str,
+    order: Order,
+    predicate: P,
+) -> StdResult<Option<RedemptionRate>> {
+    range_rates(storage, denom, None, None, order)
+        .find(|item| item.as_ref().map_or(true, &predicate))
+        .transpose()
+}
+
+/// Returns the latest matching rate of a denom with a time at or before `time`, and the
+/// earliest matching rate after it
+pub fn find_surrounding_rates<P: Fn(&RedemptionRate) -> bool>(
+    storage: &dyn Storage,
+    denom: &str,
+    time: u64,
+    predicate: P,
+) -> StdResult<(Option<RedemptionRate>, Option<RedemptionRate>)> {
+    let rates = REDEMPTION_RATES.prefix(denom);
+
+    let previous = rates
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(time)),
+            Order::Descending,
+        )
+        .map(|item| item.map(|(_, entry)| entry.into_rate(denom)))
+        .find(|item| item.as_ref().map_or(true, &predicate))
+        .transpose()?;
+    let next = rates
+        .range(
+            storage,
+            Some(Bound::exclusive(time)),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, entry)| entry.into_rate(denom)))
+        .find(|item| item.as_ref().map_or(true, &predicate))
+        .transpose()?;
+
+    Ok((previous, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Decimal, Order, StdResult};
+
+    use crate::{
+        lsr_history::{
+            add_rate, clear_history, find_rate, find_surrounding_rates, range_rates, remove_rate,
+            set_history_capacity,
+        },
+        lsr_state::{HistoryHead, RedemptionRate, HISTORY_HEADS, HISTORY_ITEM_CAP},
+    };
+
+    const DENOM: &str = "ibc/denom";
+
+    fn get_test_rate(value: &str, time: u64) -> RedemptionRate {
+        RedemptionRate {
+            denom: DENOM.to_string(),
+            redemption_rate: Decimal::from_str(value).unwrap(),
+            update_time: time,
+            anomaly_detected: false,
+            quarantined: false,
+            overridden: false,
+        }
+    }
+
+    fn get_update_times(storage: &MockStorage) -> Vec<u64> {
+        range_rates(storage, DENOM, None, None, Order::Ascending)
+            .map(|item| item.map(|rate| rate.update_time))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_add_rate() {
+        let mut storage = MockStorage::new();
+
+        for time in [3, 1, 2] {
+            add_rate(&mut storage, &get_test_rate("1", time)).unwrap();
+        }
+        assert_eq!(get_update_times(&storage), vec![1, 2, 3]);
+        assert_eq!(
+            HISTORY_HEADS.load(&storage, DENOM).unwrap(),
+            HistoryHead {
+                capacity: HISTORY_ITEM_CAP,
+                len: 3,
+            }
+        );
+
+        // A rate at an existing time replaces it
+        add_rate(&mut storage, &get_test_rate("1.1", 2)).unwrap();
+        assert_eq!(HISTORY_HEADS.load(&storage, DENOM).unwrap().len, 3);
+        let latest = find_rate(&storage, DENOM, Order::Descending, |rr| rr.update_time == 2);
+        assert_eq!(latest.unwrap(), Some(get_test_rate("1.1", 2)));
+
+        // Shrinking the history drops the oldest rates, and so do rates beyond the capacity
+        set_history_capacity(&mut storage, DENOM, 2).unwrap();
+        assert_eq!(get_update_times(&storage), vec![2, 3]);
+
+        add_rate(&mut storage, &get_test_rate("1", 4)).unwrap();
+        assert_eq!(get_update_times(&storage), vec![3, 4]);
+        assert_eq!(
+            HISTORY_HEADS.load(&storage, DENOM).unwrap(),
+            HistoryHead {
+                capacity: 2,
+                len: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_remove_rates() {
+        let mut storage = MockStorage::new();
+
+        for time in [1, 2, 3] {
+            add_rate(&mut storage, &get_test_rate("1", time)).unwrap();
+        }
+
+        let removed = remove_rate(&mut storage, DENOM, 2).unwrap();
+        assert_eq!(removed, Some(get_test_rate("1", 2)));
+        assert_eq!(remove_rate(&mut storage, DENOM, 2).unwrap(), None);
+        assert_eq!(get_update_times(&storage), vec![1, 3]);
+        assert_eq!(HISTORY_HEADS.load(&storage, DENOM).unwrap().len, 2);
+
+        clear_history(&mut storage, DENOM).unwrap();
+        assert_eq!(get_update_times(&storage), Vec::<u64>::new());
+        assert!(!HISTORY_HEADS.has(&storage, DENOM));
+    }
+
+    #[test]
+    fn test_find_rates() {
+        let mut storage = MockStorage::new();
+
+        for time in [10, 20, 30, 40] {
+            let mut rate = get_test_rate("1", time);
+            rate.quarantined = time == 20 || time == 40;
+            add_rate(&mut storage, &rate).unwrap();
+        }
+        let served = |rr: &RedemptionRate| !rr.quarantined;
+
+        let latest = find_rate(&storage, DENOM, Order::Descending, served).unwrap();
+        assert_eq!(latest.map(|rr| rr.update_time), Some(30));
+
+        let times = |(previous, next): (Option<RedemptionRate>, Option<RedemptionRate>)| {
+            (
+                previous.map(|rr| rr.update_time),
+                next.map(|rr| rr.update_time),
+            )
+        };
+        let surrounding = find_surrounding_rates(&storage, DENOM, 25, served).unwrap();
+        assert_eq!(times(surrounding), (Some(10), Some(30)));
+        let surrounding = find_surrounding_rates(&storage, DENOM, 30, served).unwrap();
+        assert_eq!(times(surrounding), (Some(30), None));
+        let surrounding = find_surrounding_rates(&storage, DENOM, 5, served).unwrap();
+        assert_eq!(times(surrounding), (None, Some(10)));
+
+        let rates = range_rates(&storage, DENOM, Some(10), Some(40), Order::Descending)
+            .map(|item| item.map(|rate| rate.update_time))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(rates, vec![30, 20]);
+    }
+}
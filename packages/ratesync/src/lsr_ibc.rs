@@ -0,0 +1,26 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::IbcOrder;
+
+use crate::lsr_msg::{LiquidStakeRateEntry, LiquidStakeRatesResponse};
+
+/// Channel version of the ratesync packet format, both ends must agree on it
+pub const RATESYNC_VERSION: &str = "ratesync-1";
+
+/// Rates of an epoch are independent of the previous packets, so channels are unordered
+pub const RATESYNC_ORDERING: IbcOrder = IbcOrder::Unordered;
+
+/// Packets sent by the controller chain over a ratesync channel
+#[cw_serde]
+pub enum RatesyncPacket {
+    /// Set c-values of several denom pairs, stored as a whole or not at all
+    LiquidStakeRates { rates: Vec<LiquidStakeRateEntry> },
+}
+
+/// Acknowledgement of a ratesync packet
+#[cw_serde]
+pub enum RatesyncAck {
+    /// The rates were stored, with the anomaly results in the order of the entries
+    Result(LiquidStakeRatesResponse),
+    /// The packet was rejected and nothing was stored
+    Error(String),
+}
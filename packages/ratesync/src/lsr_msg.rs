@@ -1,9 +1,15 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary, Decimal};
+use cosmwasm_std::{Addr, Binary, Decimal, Order};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::lsr_state::RedemptionRate;
+use crate::{
+    lsr_round::{QuorumConfig, RoundStatus},
+    lsr_state::{
+        AnomalyPolicy, AnomalyStrategy, DenomMetadata, DenomTrace, HookFailure, PauseFlags,
+        RedemptionRate, Role, ThresholdMode, TransferHop,
+    },
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -22,7 +28,8 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Set c-value for denom pair
+    /// Set c-value for denom pair. With a quorum configured, the c-value is submitted to
+    /// the round of the denom and time instead, and committed once the quorum agrees.
     LiquidStakeRate {
         /// Default bond denom
         default_bond_denom: String,
@@ -32,6 +39,16 @@ pub enum ExecuteMsg {
         c_value: Decimal,
         /// time
         controller_chain_time: u64,
+        /// Full IBC trace of the stkToken on the oracle chain, overrides its transfer path
+        denom_trace: Option<DenomTrace>,
+    },
+    /// Set c-values of several denom pairs at once. The batch is rejected as a whole if any
+    /// entry fails, the response data holds a LiquidStakeRatesResponse. With a quorum
+    /// configured, the entries are submitted to their rounds and the response data holds
+    /// one result per entry, with the round status of its submission.
+    LiquidStakeRates {
+        /// One entry per stkToken
+        rates: Vec<LiquidStakeRateEntry>,
     },
     /// Update config
     UpdateConfig {
@@ -48,9 +65,269 @@ pub enum ExecuteMsg {
         deviation_count_limit: u64,
         /// Deviation Threshold
         deviation_threshold: Decimal,
+        /// Whether the threshold is relative to the baseline or absolute, defaults to the
+        /// denom's current mode, or to the default config's mode for a new denom
+        threshold_mode: Option<ThresholdMode>,
+        /// Handling of anomalous rates, defaults to the denom's current policy, or to the
+        /// default config's policy for a new denom
+        policy: Option<AnomalyPolicy>,
+        /// Baseline the new rate is compared against, defaults to the denom's current
+        /// strategy, or to the default config's strategy for a new denom
+        strategy: Option<AnomalyStrategy>,
+        /// Leave rates flagged as anomalous out of the baseline, defaults to the denom's
+        /// current setting, or to the default config's setting for a new denom
+        exclude_flagged: Option<bool>,
+    },
+    /// Set the anomaly config given to newly seen denoms
+    SetDefaultAnomalyConfig {
+        /// Deviation Count limit
+        deviation_count_limit: u64,
+        /// Deviation Threshold
+        deviation_threshold: Decimal,
+        /// Whether the threshold is relative to the baseline or absolute, defaults to the
+        /// current default config's mode
+        threshold_mode: Option<ThresholdMode>,
+        /// Handling of anomalous rates, defaults to the current default config's policy
+        policy: Option<AnomalyPolicy>,
+        /// Baseline the new rate is compared against, defaults to the current default
+        /// config's strategy
+        strategy: Option<AnomalyStrategy>,
+        /// Leave rates flagged as anomalous out of the baseline, defaults to the current
+        /// default config's setting
+        exclude_flagged: Option<bool>,
+    },
+    /// Register the transfer path of an stkToken, overriding the path from the config
+    SetTransferPath {
+        /// stkToken denom
+        stk_denom: String,
+        /// Transfer Port ID
+        transfer_port_i_d: String,
+        /// Transfer Channel ID
+        transfer_channel_i_d: String,
+        /// Further hops towards the chain the stkToken originated on
+        intermediate_hops: Option<Vec<TransferHop>>,
+    },
+    /// Remove the registered transfer path of an stkToken, falling back to the config
+    RemoveTransferPath {
+        /// stkToken denom
+        stk_denom: String,
+    },
+    /// Set the number of rates kept in the history of an stkToken, dropping the oldest
+    /// rates if the history shrinks
+    SetHistoryCapacity {
+        /// stkToken denom
+        stk_denom: String,
+        /// Maximum number of rates to keep
+        capacity: u64,
+    },
+    /// Set the maximum age of the latest rate of an stkToken before the RedemptionRate
+    /// query considers it stale
+    SetMaxAge {
+        /// stkToken denom
+        stk_denom: String,
+        /// Maximum age in seconds, None disables the staleness check
+        max_age_seconds: Option<u64>,
+    },
+    /// Propose a new owner, who has to accept before the transfer takes effect
+    ProposeOwnership {
+        /// Proposed owner address
+        new_owner: String,
+        /// Unix timestamp in seconds after which the proposal can no longer be accepted
+        expires_at: Option<u64>,
+    },
+    /// Accept the pending ownership transfer as the proposed owner
+    AcceptOwnership {},
+    /// Cancel the pending ownership transfer
+    CancelOwnership {},
+    /// Grant a role to an address
+    GrantRole {
+        /// Role to grant
+        role: Role,
+        /// Address receiving the role
+        address: String,
+    },
+    /// Revoke a role from an address
+    RevokeRole {
+        /// Role to revoke
+        role: Role,
+        /// Address losing the role
+        address: String,
+    },
+    /// Quarantine a served rate until the admin resolves it
+    QuarantineRate {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// Update time of the rate
+        update_time: u64,
+    },
+    /// Allow ratesync channels on an IBC connection
+    AllowIbcConnection {
+        /// Connection ID on the oracle chain
+        connection_id: String,
+    },
+    /// Stop accepting packets from an IBC connection, open channels stay open but
+    /// their packets are acknowledged with an error
+    DisallowIbcConnection {
+        /// Connection ID on the oracle chain
+        connection_id: String,
+    },
+    /// Pause or resume ingestion and serving of rates, for a single denom or globally
+    SetPause {
+        /// The ibc hash of an stkToken as it lives on the oracle chain, None pauses globally
+        denom: Option<String>,
+        /// Refuse new rates, None leaves the flag unchanged
+        ingestion: Option<bool>,
+        /// Fail the RedemptionRate query, None leaves the flag unchanged
+        serving: Option<bool>,
+    },
+    /// Approve or discard a quarantined rate
+    ResolveQuarantinedRate {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// Update time of the quarantined rate
+        update_time: u64,
+        /// Serve the rate if true, remove it from history otherwise
+        approve: bool,
+    },
+    /// Delete the rate history and per-denom settings of a stkToken, e.g. one registered
+    /// under a wrong channel
+    RemoveDenom {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// Archive the final rate so that rate queries fail with a retired error and new
+        /// rates are refused. Otherwise the denom is forgotten, along with any tombstone.
+        tombstone: Option<bool>,
+    },
+    /// Notify a contract of every new rate of an stkToken with a RateHookMsg
+    AddSubscriber {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// Subscriber contract address
+        address: String,
+    },
+    /// Stop notifying a contract of new rates
+    RemoveSubscriber {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// Subscriber contract address
+        address: String,
+    },
+    /// Require feeders to agree on rates before they are committed. The quorum can't be
+    /// more than the number of feeders, who can't be revoked below it. Ratesync channels
+    /// also submit rates to the rounds but don't count toward that limit, as they can be
+    /// closed at any time.
+    SetQuorumConfig {
+        /// None commits rates as posted
+        quorum_config: Option<QuorumConfig>,
     },
 }
 
+/// Hooks sent to subscriber contracts, which accept them as part of their ExecuteMsg.
+/// A failing hook is recorded against the subscriber and doesn't revert the rate.
+#[cw_serde]
+pub enum RateHookMsg {
+    /// A rate was accepted and is served by the RedemptionRate query, quarantined rates
+    /// aren't sent
+    RateUpdated {
+        /// The ibc hash of the stkToken as it lives on the oracle chain
+        denom: String,
+        /// The c-value of the stkToken
+        redemption_rate: Decimal,
+        /// Unix timestamp of the rate
+        update_time: u64,
+    },
+}
+
+/// A single c-value of a LiquidStakeRates batch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidStakeRateEntry {
+    /// Default bond denom
+    pub default_bond_denom: String,
+    /// Stake denom
+    pub stk_denom: String,
+    /// Exchange rate for denom pair
+    pub c_value: Decimal,
+    /// time
+    pub controller_chain_time: u64,
+    /// Full IBC trace of the stkToken on the oracle chain, overrides its transfer path
+    pub denom_trace: Option<DenomTrace>,
+}
+
+/// Response data of a LiquidStakeRates batch, in the order of the entries
+#[cw_serde]
+pub struct LiquidStakeRatesResponse {
+    pub results: Vec<LiquidStakeRateResult>,
+}
+
+#[cw_serde]
+pub struct LiquidStakeRateResult {
+    /// Stake denom
+    pub stk_denom: String,
+    /// The ibc hash of the stkToken on the oracle chain
+    pub stk_denom_ibc_hash: String,
+    /// Whether the rate failed anomaly detection
+    pub anomaly_detected: bool,
+    /// Whether the rate was quarantined by the denom's anomaly policy
+    pub quarantined: bool,
+    /// Status of the round the rate was submitted to, None if the rate was stored as
+    /// posted without a quorum
+    pub round_status: Option<RoundStatus>,
+}
+
+/// Overrides for chain governance, e.g. to recover a deployment whose owner key was lost
+#[cw_serde]
+pub enum SudoMsg {
+    /// Replace the owner, dropping any pending ownership transfer
+    SetOwner {
+        /// New owner address
+        owner: String,
+    },
+    /// Grant a role to an address
+    GrantRole {
+        /// Role to grant
+        role: Role,
+        /// Address receiving the role
+        address: String,
+    },
+    /// Revoke a role from an address
+    RevokeRole {
+        /// Role to revoke
+        role: Role,
+        /// Address losing the role
+        address: String,
+    },
+    /// Pause or resume ingestion and serving of rates, for a single denom or globally
+    SetPause {
+        /// The ibc hash of an stkToken as it lives on the oracle chain, None pauses globally
+        denom: Option<String>,
+        /// Refuse new rates, None leaves the flag unchanged
+        ingestion: Option<bool>,
+        /// Fail the RedemptionRate query, None leaves the flag unchanged
+        serving: Option<bool>,
+    },
+    /// Store a rate for a tracked denom, marked as overridden. It skips the pause flags
+    /// and anomaly detection, and replaces the rate already stored at `update_time`.
+    OverrideRedemptionRate {
+        /// The ibc hash of an stkToken as it lives on the oracle chain
+        denom: String,
+        /// The c-value of the stkToken
+        redemption_rate: Decimal,
+        /// Unix timestamp of the rate, at or after the latest rate to be served as the latest
+        update_time: u64,
+    },
+}
+
+/// Migrates the contract state to the current contract version
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {
+    /// Denoms of stkTokens tracked before their denoms were stored along with their rates
+    /// (before 1.4.0), so that they are indexed by bond denom without waiting for a new
+    /// rate. Each stkToken must already have rates or an anomaly config.
+    #[serde(default)]
+    pub denom_metadata: Vec<DenomMetadata>,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -66,17 +343,156 @@ pub enum QueryMsg {
         denom: String,
     },
 
-    /// Returns the redemption rate of an stkToken
+    /// Returns the anomaly config given to newly seen denoms
+    #[returns(AnomalyConfigResponse)]
+    DefaultAnomalyConfig {},
+
+    /// Returns the history capacity of an stkToken
+    #[returns(HistoryCapacityResponse)]
+    HistoryCapacity {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the pending ownership transfer
+    #[returns(crate::ownership::PendingOwnershipResponse)]
+    PendingOwnership {},
+
+    /// Returns the addresses granted a role
+    #[returns(RoleMembersResponse)]
+    RoleMembers { role: Role },
+
+    /// Returns the maximum age of the latest rate of an stkToken
+    #[returns(MaxAgeResponse)]
+    MaxAge {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the global pause flags, the flags of a denom and the resulting pause state
+    #[returns(PauseStatusResponse)]
+    PauseStatus {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: Option<String>,
+    },
+
+    /// Returns the tombstone of a removed denom
+    #[returns(crate::lsr_state::DenomTombstone)]
+    RetiredDenom {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the subscribers of an stkToken along with their hook failures
+    #[returns(SubscribersResponse)]
+    Subscribers {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the quorum feeders have to reach on a rate
+    #[returns(QuorumConfigResponse)]
+    QuorumConfig {},
+
+    /// Returns the submissions of the feeders for the rate of an stkToken at a time
+    #[returns(crate::lsr_round::Round)]
+    Round {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+        /// Controller chain time the feeders submitted rates for
+        update_time: u64,
+    },
+
+    /// Returns the IBC connections ratesync channels are allowed on
+    #[returns(IbcConnectionsResponse)]
+    IbcConnections {},
+
+    /// Returns the transfer path used to derive the IBC hash of an stkToken
+    #[returns(TransferPathResponse)]
+    TransferPath {
+        /// stkToken denom as it lives on the controller chain
+        stk_denom: String,
+    },
+
+    /// Returns all registered transfer paths
+    #[returns(TransferPathsResponse)]
+    TransferPaths {},
+
+    /// Returns the denoms of an stkToken as posted with its latest rate
+    #[returns(DenomInfoResponse)]
+    DenomInfo {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the stkTokens whose latest rate was posted with a bond denom, ordered by
+    /// ibc hash
+    #[returns(StkDenomsResponse)]
+    StkDenomsByBondDenom {
+        /// Default bond denom, e.g. uatom
+        default_bond_denom: String,
+    },
+
+    /// Returns the latest non-quarantined redemption rate of an stkToken, or their
+    /// time-weighted average if the params contain a TWAP window
     #[returns(RedemptionRateResponse)]
     RedemptionRate {
         /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
         /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
         denom: String,
-        /// Params should always be None, but was included in this query
+        /// Optional json encoded RedemptionRateParams, included in this query
         /// to align with other price oracles that take additional parameters such as TWAP
         params: Option<Binary>,
     },
 
+    /// Returns the latest non-quarantined rate of an stkToken as its price in its bond denom,
+    /// following the Mars oracle Price query. Fails if the rate is stale or serving is paused.
+    #[returns(oracle::PriceResponse)]
+    Price {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+        /// Purpose of the price, accepted for compatibility with the Mars oracle
+        kind: Option<oracle::ActionKind>,
+    },
+
+    /// Returns the price of an stkToken along with its age, staleness and confidence.
+    /// Stale rates are returned with the stale flag set rather than failing the query.
+    #[returns(oracle::PriceInfoResponse)]
+    PriceInfo {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+    },
+
+    /// Returns the non-quarantined redemption rate of an stkToken at a point in time
+    #[returns(RedemptionRateAtResponse)]
+    RedemptionRateAt {
+        /// The denom should be the ibc hash of an stkToken as it lives on the oracle chain
+        /// (e.g. ibc/{hash(transfer/channel-0/stkuatom)} on Osmosis)
+        denom: String,
+        /// Unix timestamp in seconds, between the oldest and latest rate in the history
+        time: u64,
+        /// How to value a time between two rates, defaults to the previous rate
+        mode: Option<InterpolationMode>,
+    },
+
+    /// Returns every tracked stkToken with its latest non-quarantined rate, ordered by denom
+    #[returns(AllRedemptionRatesResponse)]
+    AllRedemptionRates {
+        /// The ibc hash of the last stkToken of the previous page
+        start_after: Option<String>,
+        /// Maximum number of stkTokens to return, defaults to 10 and is capped at 30
+        limit: Option<u32>,
+    },
+
     /// Returns a list of redemption rates over time for an stkToken
     #[returns(RedemptionRates)]
     HistoricalRedemptionRates {
@@ -86,8 +502,14 @@ pub enum QueryMsg {
         /// Params should always be None, but was included in this query
         /// to align with other price oracles that take additional parameters such as TWAP
         params: Option<Binary>,
-        /// Optional limit on the number of entries to return
+        /// Maximum number of entries to return, defaults to 10 and is capped at 30
         limit: Option<u64>,
+        /// Only return rates updated after this unix timestamp
+        start_after: Option<u64>,
+        /// Only return rates updated before this unix timestamp
+        end_before: Option<u64>,
+        /// Order of the rates, defaults to newest first
+        order: Option<RateOrder>,
     },
 }
 
@@ -107,15 +529,386 @@ pub struct AnomalyConfigResponse {
     pub deviation_count_limit: u64,
     /// Deviation Threshold
     pub deviation_threshold: Decimal,
+    /// Whether the threshold is relative to the baseline or absolute
+    pub threshold_mode: ThresholdMode,
+    /// Handling of anomalous rates
+    pub policy: AnomalyPolicy,
+    /// Baseline the new rate is compared against
+    pub strategy: AnomalyStrategy,
+    /// Whether rates flagged as anomalous are left out of the baseline
+    pub exclude_flagged: bool,
+}
+
+#[cw_serde]
+pub struct HistoryCapacityResponse {
+    /// Maximum number of rates kept
+    pub capacity: u64,
+    /// Number of rates currently kept
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct MaxAgeResponse {
+    /// Maximum age in seconds, None if the staleness check is disabled
+    pub max_age_seconds: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    /// Flags applying to every denom
+    pub global: PauseFlags,
+    /// Flags of the queried denom, None if no denom was queried
+    pub denom: Option<PauseFlags>,
+    /// Whether new rates are refused for the queried denom, or globally if none was queried
+    pub ingestion_paused: bool,
+    /// Whether the RedemptionRate query fails for the queried denom, or globally if none
+    /// was queried
+    pub serving_paused: bool,
+}
+
+#[cw_serde]
+pub struct SubscribersResponse {
+    pub subscribers: Vec<SubscriberInfo>,
+}
+
+#[cw_serde]
+pub struct SubscriberInfo {
+    /// Subscriber contract address
+    pub address: Addr,
+    /// Number of hooks the subscriber failed since it subscribed
+    pub failure_count: u64,
+    /// Latest failed hook, None if the subscriber never failed
+    pub last_failure: Option<HookFailure>,
+}
+
+#[cw_serde]
+pub struct QuorumConfigResponse {
+    /// None if rates are committed as posted
+    pub quorum_config: Option<QuorumConfig>,
+}
+
+#[cw_serde]
+pub struct IbcConnectionsResponse {
+    pub connection_ids: Vec<String>,
+}
+
+#[cw_serde]
+pub struct TransferPathResponse {
+    /// stkToken denom as it lives on the controller chain
+    pub stk_denom: String,
+    /// Transfer Port ID
+    pub transfer_port_i_d: String,
+    /// Transfer Channel ID
+    pub transfer_channel_i_d: String,
+    /// Further hops towards the chain the stkToken originated on
+    pub intermediate_hops: Vec<TransferHop>,
+    /// The ibc hash of the stkToken on the oracle chain
+    pub ibc_denom: String,
+    /// False if the denom uses the transfer path from the config
+    pub registered: bool,
+}
+
+#[cw_serde]
+pub struct TransferPathsResponse {
+    pub transfer_paths: Vec<TransferPathResponse>,
+}
+
+#[cw_serde]
+pub struct DenomInfoResponse {
+    /// stkToken denom as an IBC hash, as it appears on the oracle chain
+    pub denom: String,
+    /// stkToken denom as it lives on the controller chain
+    pub stk_denom: String,
+    /// Default bond denom
+    pub default_bond_denom: String,
+}
+
+#[cw_serde]
+pub struct StkDenomsResponse {
+    pub stk_denoms: Vec<DenomInfoResponse>,
+}
+
+/// Params of the RedemptionRate query
+#[cw_serde]
+#[derive(Default)]
+pub struct RedemptionRateParams {
+    /// Handling of a rate older than the denom's max age, defaults to strict
+    pub staleness_mode: Option<StalenessMode>,
+    /// Return the time-weighted average rate over this many seconds up to the block time
+    /// instead of the latest rate
+    pub twap_window_seconds: Option<u64>,
+}
+
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum StalenessMode {
+    /// Fail the query if the rate is stale
+    #[default]
+    Strict,
+    /// Return the rate with the stale flag set
+    Lenient,
+}
+
+#[cw_serde]
+pub struct AllRedemptionRatesResponse {
+    pub redemption_rates: Vec<DenomRedemptionRate>,
+}
+
+/// The latest rate of an stkToken. Rate fields are None if no rate is served for the
+/// denom, including while its serving is paused, and the denoms are None if they were
+/// never recorded for it.
+#[cw_serde]
+pub struct DenomRedemptionRate {
+    /// stkToken denom as an IBC hash, as it appears on the oracle chain
+    pub denom: String,
+    /// stkToken denom as it lives on the controller chain
+    pub stk_denom: Option<String>,
+    /// Default bond denom
+    pub default_bond_denom: Option<String>,
+    pub redemption_rate: Option<Decimal>,
+    pub update_time: Option<u64>,
+    /// Whether the latest rate was flagged as anomalous
+    pub anomaly_detected: bool,
+    /// True if the latest rate is older than the denom's max age
+    pub stale: bool,
+    /// True if serving the rates of the denom is paused
+    pub paused: bool,
+}
+
+/// Order of the HistoricalRedemptionRates query
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum RateOrder {
+    /// Oldest first
+    Ascending,
+    /// Newest first
+    #[default]
+    Descending,
+}
+
+impl From<RateOrder> for Order {
+    fn from(order: RateOrder) -> Self {
+        match order {
+            RateOrder::Ascending => Order::Ascending,
+            RateOrder::Descending => Order::Descending,
+        }
+    }
+}
+
+/// How the RedemptionRateAt query values a time between two rates
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum InterpolationMode {
+    /// The rate last updated at or before the time
+    #[default]
+    Previous,
+    /// Linear interpolation between the rates updated before and after the time
+    Linear,
+}
+
+#[cw_serde]
+pub struct RedemptionRateAtResponse {
+    pub redemption_rate: Decimal,
+    /// The queried time
+    pub time: u64,
+    /// Update time of the rate at or before the queried time
+    pub update_time: u64,
 }
 
 #[cw_serde]
 pub struct RedemptionRateResponse {
     pub redemption_rate: Decimal,
     pub update_time: u64,
+    /// True if the rate is older than the denom's max age. Left out when false, so the
+    /// response stays readable by consumers that only know the Stride oracle fields.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct RedemptionRates {
     pub redemption_rates: Vec<RedemptionRate>,
+    /// Update time of the last returned rate if the limit cut the page short. Pass it as
+    /// `end_before` for the next page when newest first, or as `start_after` when oldest first.
+    #[serde(default)]
+    pub next_cursor: Option<u64>,
+}
+
+/// Response shapes of the common CosmWasm oracle price sources, so lending protocols can
+/// read stkToken prices without a custom adapter
+pub mod oracle {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Decimal;
+
+    /// Purpose of a price query, as passed by the Mars oracle. Rates are served the same
+    /// way for every kind.
+    #[cw_serde]
+    #[derive(Default, Copy)]
+    pub enum ActionKind {
+        #[default]
+        Default,
+        Liquidation,
+    }
+
+    /// Price of an stkToken in its bond denom, shaped like the Mars oracle price response
+    #[cw_serde]
+    pub struct PriceResponse {
+        /// stkToken denom as an IBC hash, as it appears on the oracle chain
+        pub denom: String,
+        /// The c-value of the stkToken, i.e. how many bond denom units one stkToken is worth
+        pub price: Decimal,
+    }
+
+    #[cw_serde]
+    pub struct PriceInfoResponse {
+        /// stkToken denom as an IBC hash, as it appears on the oracle chain
+        pub denom: String,
+        /// Bond denom the price is quoted in, None if it was never recorded for the denom
+        pub quote_denom: Option<String>,
+        /// The c-value of the stkToken
+        pub price: Decimal,
+        /// Unix timestamp of the rate
+        pub update_time: u64,
+        /// Seconds between the update time and the block time
+        pub age_seconds: u64,
+        /// Maximum age of the denom, None if the staleness check is disabled
+        pub max_age_seconds: Option<u64>,
+        /// True if the rate is older than the denom's max age
+        pub stale: bool,
+        pub confidence: Confidence,
+    }
+
+    /// How far the served rate can be trusted
+    #[cw_serde]
+    #[derive(Copy)]
+    pub enum Confidence {
+        /// Posted by a feeder and passed anomaly detection
+        High,
+        /// Flagged by anomaly detection and served under the denom's policy
+        Low,
+        /// Set by chain governance through sudo
+        Overridden,
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::str::FromStr;
+
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::{from_json, to_json_vec, Decimal};
+
+        use crate::lsr_msg::{
+            oracle::{ActionKind, Confidence, PriceInfoResponse, PriceResponse},
+            QueryMsg, RedemptionRateResponse,
+        };
+
+        const DENOM: &str = "ibc/2256F6C3E947DED8F20F1535BAC94D00027930FFB659244BFD63F251E97C23CC";
+
+        // Response types as declared by the consumers, which refuse unknown fields
+        #[cw_serde]
+        struct MarsPriceResponse {
+            denom: String,
+            price: Decimal,
+        }
+
+        #[cw_serde]
+        struct StrideRedemptionRateResponse {
+            redemption_rate: Decimal,
+            update_time: u64,
+        }
+
+        #[test]
+        fn test_consumer_queries() {
+            let msg: QueryMsg = from_json(format!(r#"{{"price":{{"denom":"{DENOM}"}}}}"#)).unwrap();
+            assert_eq!(
+                msg,
+                QueryMsg::Price {
+                    denom: DENOM.to_string(),
+                    kind: None,
+                }
+            );
+
+            let msg: QueryMsg = from_json(format!(
+                r#"{{"price":{{"denom":"{DENOM}","kind":"liquidation"}}}}"#
+            ))
+            .unwrap();
+            assert_eq!(
+                msg,
+                QueryMsg::Price {
+                    denom: DENOM.to_string(),
+                    kind: Some(ActionKind::Liquidation),
+                }
+            );
+
+            let msg: QueryMsg = from_json(format!(
+                r#"{{"redemption_rate":{{"denom":"{DENOM}","params":null}}}}"#
+            ))
+            .unwrap();
+            assert_eq!(
+                msg,
+                QueryMsg::RedemptionRate {
+                    denom: DENOM.to_string(),
+                    params: None,
+                }
+            );
+        }
+
+        #[test]
+        fn test_consumer_responses() {
+            let price = PriceResponse {
+                denom: DENOM.to_string(),
+                price: Decimal::from_str("1.05").unwrap(),
+            };
+            let fixture = format!(r#"{{"denom":"{DENOM}","price":"1.05"}}"#);
+            assert_eq!(to_json_vec(&price).unwrap(), fixture.as_bytes());
+            assert_eq!(from_json::<PriceResponse>(&fixture).unwrap(), price);
+            let mars: MarsPriceResponse = from_json(to_json_vec(&price).unwrap()).unwrap();
+            assert_eq!(mars.price, price.price);
+
+            let mut rate = RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("1.05").unwrap(),
+                update_time: 1700000000,
+                stale: false,
+            };
+            let fixture = r#"{"redemption_rate":"1.05","update_time":1700000000}"#;
+            assert_eq!(to_json_vec(&rate).unwrap(), fixture.as_bytes());
+            assert_eq!(from_json::<RedemptionRateResponse>(fixture).unwrap(), rate);
+            let stride: StrideRedemptionRateResponse =
+                from_json(to_json_vec(&rate).unwrap()).unwrap();
+            assert_eq!(stride.update_time, rate.update_time);
+
+            // Only lenient queries return stale rates, with the extra field
+            rate.stale = true;
+            let fixture = r#"{"redemption_rate":"1.05","update_time":1700000000,"stale":true}"#;
+            assert_eq!(to_json_vec(&rate).unwrap(), fixture.as_bytes());
+        }
+
+        #[test]
+        fn test_price_info_response() {
+            let fixture = format!(
+                r#"{{"denom":"{DENOM}","quote_denom":"uatom","price":"1.05","update_time":1700000000,"age_seconds":60,"max_age_seconds":3600,"stale":false,"confidence":"low"}}"#
+            );
+            let info: PriceInfoResponse = from_json(&fixture).unwrap();
+            assert_eq!(
+                info,
+                PriceInfoResponse {
+                    denom: DENOM.to_string(),
+                    quote_denom: Some("uatom".to_string()),
+                    price: Decimal::from_str("1.05").unwrap(),
+                    update_time: 1700000000,
+                    age_seconds: 60,
+                    max_age_seconds: Some(3600),
+                    stale: false,
+                    confidence: Confidence::Low,
+                }
+            );
+            assert_eq!(to_json_vec(&info).unwrap(), fixture.as_bytes());
+        }
+    }
 }
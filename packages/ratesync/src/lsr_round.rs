@@ -0,0 +1,459 @@
+use std::fmt;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::{lsr_anomaly::median, lsr_error::ContractError, lsr_state::DenomMetadata};
+
+/// Agreement required between feeders before a posted rate is committed to the history
+#[cw_serde]
+pub struct QuorumConfig {
+    /// Minimum number of submissions within the tolerance of their median
+    pub quorum: u64,
+    /// Maximum deviation of a submission from the median, relative to the median
+    pub tolerance: Decimal,
+}
+
+impl QuorumConfig {
+    /// A quorum of one would commit every rate as posted, which is what running without a
+    /// quorum config does, so at least two submissions have to agree
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.quorum < 2 {
+            return Err(ContractError::InvalidQuorumConfig {
+                reason: "quorum must be at least two".to_string(),
+            });
+        }
+
+        // Any rate would agree with the median within a tolerance of one
+        if self.tolerance >= Decimal::one() {
+            return Err(ContractError::InvalidQuorumConfig {
+                reason: "tolerance must be less than one".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether a rate is within the tolerance of the reference rate
+    pub fn agrees(&self, reference: Decimal, redemption_rate: Decimal) -> bool {
+        reference.abs_diff(redemption_rate) <= reference * self.tolerance
+    }
+}
+
+/// Where a submission stands in its round
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum RoundStatus {
+    /// The round is waiting for a quorum
+    Buffered,
+    /// The round committed a rate the submission agrees with
+    Committed,
+    /// The round committed a rate the submission disagrees with
+    Dissenting,
+    /// The anomaly policy of the denom refused the rate the round agreed on
+    Rejected,
+}
+
+impl fmt::Display for RoundStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundStatus::Buffered => write!(f, "buffered"),
+            RoundStatus::Committed => write!(f, "committed"),
+            RoundStatus::Dissenting => write!(f, "dissenting"),
+            RoundStatus::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+/// A rate posted by a feeder
+#[cw_serde]
+pub struct Submission {
+    pub feeder: Addr,
+    /// The c-value posted by the feeder
+    pub redemption_rate: Decimal,
+    /// The denoms posted along with the rate
+    #[serde(default)]
+    pub metadata: DenomMetadata,
+}
+
+/// Submissions of the feeders for the rate of a denom at an update time
+#[cw_serde]
+#[derive(Default)]
+pub struct Round {
+    /// Block time of the first submission, in unix seconds
+    #[serde(default)]
+    pub opened_at: u64,
+    /// Submissions in the order they were received, one per feeder
+    pub submissions: Vec<Submission>,
+    /// Median committed to the history, None until a quorum agrees on it
+    pub committed_rate: Option<Decimal>,
+    /// Denoms of the submissions that committed the rate
+    #[serde(default)]
+    pub committed_metadata: Option<DenomMetadata>,
+    /// Feeders whose submission is outside the tolerance of the committed rate or posted
+    /// other denoms
+    pub dissenters: Vec<Addr>,
+    /// Set when the median the quorum agreed on was refused by the anomaly policy of the
+    /// denom. Later submissions are recorded but can't commit a rate.
+    #[serde(default)]
+    pub rejected: bool,
+}
+
+impl Round {
+    pub fn has_submitted(&self, feeder: &Addr) -> bool {
+        self.submissions.iter().any(|s| s.feeder == *feeder)
+    }
+
+    /// Whether the round was opened before `opened_before` and didn't commit a rate
+    pub fn is_expired(&self, opened_before: u64) -> bool {
+        self.committed_rate.is_none() && self.opened_at < opened_before
+    }
+
+    /// Status of the submission of a feeder
+    pub fn status(&self, feeder: &Addr) -> RoundStatus {
+        if self.rejected {
+            RoundStatus::Rejected
+        } else if self.committed_rate.is_none() {
+            RoundStatus::Buffered
+        } else if self.dissenters.contains(feeder) {
+            RoundStatus::Dissenting
+        } else {
+            RoundStatus::Committed
+        }
+    }
+
+    /// Adds a submission and commits the median of the submissions posting the same denoms
+    /// once a quorum of them agrees with it, returning the median if this submission
+    /// committed it. Submissions received after the commit are only checked against the
+    /// committed rate and denoms.
+    pub fn submit(&mut self, config: &QuorumConfig, submission: Submission) -> Option<Decimal> {
+        let feeder = submission.feeder.clone();
+        let metadata = submission.metadata.clone();
+        let redemption_rate = submission.redemption_rate;
+        self.submissions.push(submission);
+
+        if self.rejected {
+            return None;
+        }
+
+        if let Some(committed_rate) = self.committed_rate {
+            let same_denoms = self.committed_metadata.as_ref() == Some(&metadata);
+            if !same_denoms || !config.agrees(committed_rate, redemption_rate) {
+                self.dissenters.push(feeder);
+            }
+            return None;
+        }
+
+        // Only the submissions posting the same denoms count toward the quorum
+        let rates: Vec<Decimal> = self
+            .submissions
+            .iter()
+            .filter(|s| s.metadata == metadata)
+            .map(|s| s.redemption_rate)
+            .collect();
+        let median = median(&rates)?;
+        let agreeing = rates
+            .iter()
+            .filter(|rate| config.agrees(median, **rate))
+            .count() as u64;
+        if agreeing < config.quorum {
+            return None;
+        }
+
+        self.committed_rate = Some(median);
+        self.dissenters = self
+            .submissions
+            .iter()
+            .filter(|s| s.metadata != metadata || !config.agrees(median, s.redemption_rate))
+            .map(|s| s.feeder.clone())
+            .collect();
+        self.committed_metadata = Some(metadata);
+
+        Some(median)
+    }
+
+    /// Drops the rate committed by the latest submission, which the denom refused
+    pub fn reject(&mut self) {
+        self.committed_rate = None;
+        self.committed_metadata = None;
+        self.dissenters.clear();
+        self.rejected = true;
+    }
+}
+
+/// Quorum the feeders have to reach on a rate. Without one, rates are committed as
+/// posted. Rates relayed over a ratesync channel are submitted with the channel ID as
+/// their feeder.
+pub const QUORUM_CONFIG: Item<QuorumConfig> = Item::new("quorum_config");
+
+/// Rounds of every stkToken, keyed by (stkToken ibc hash, update time)
+pub const ROUNDS: Map<(&str, u64), Round> = Map::new("rounds");
+
+/// Removes the rounds of a denom for update times before `update_time`, along with the
+/// rounds opened before `opened_before` that didn't commit a rate
+pub fn prune_rounds(
+    storage: &mut dyn Storage,
+    denom: &str,
+    update_time: u64,
+    opened_before: u64,
+) -> StdResult<()> {
+    let mut update_times = ROUNDS
+        .prefix(denom)
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive(update_time)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for item in ROUNDS.prefix(denom).range(
+        storage,
+        Some(Bound::inclusive(update_time)),
+        None,
+        Order::Ascending,
+    ) {
+        let (update_time, round) = item?;
+        if round.is_expired(opened_before) {
+            update_times.push(update_time);
+        }
+    }
+
+    for update_time in update_times {
+        ROUNDS.remove(storage, (denom, update_time));
+    }
+
+    Ok(())
+}
+
+/// Removes every round of a denom
+pub fn clear_rounds(storage: &mut dyn Storage, denom: &str) -> StdResult<()> {
+    let update_times = ROUNDS
+        .prefix(denom)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for update_time in update_times {
+        ROUNDS.remove(storage, (denom, update_time));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Addr, Decimal, Order, StdResult};
+
+    use crate::{
+        lsr_error::ContractError,
+        lsr_round::{
+            clear_rounds, prune_rounds, QuorumConfig, Round, RoundStatus, Submission, ROUNDS,
+        },
+        lsr_state::DenomMetadata,
+    };
+
+    const DENOM: &str = "ibc/denom";
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn submission(feeder: &str, redemption_rate: &str, default_bond_denom: &str) -> Submission {
+        Submission {
+            feeder: Addr::unchecked(feeder),
+            redemption_rate: dec(redemption_rate),
+            metadata: DenomMetadata {
+                stk_denom: "stk/somecoin".to_string(),
+                default_bond_denom: default_bond_denom.to_string(),
+            },
+        }
+    }
+
+    fn get_quorum_config() -> QuorumConfig {
+        QuorumConfig {
+            quorum: 2,
+            tolerance: Decimal::percent(1),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(get_quorum_config().validate(), Ok(()));
+        for quorum in [0, 1] {
+            assert_eq!(
+                QuorumConfig {
+                    quorum,
+                    tolerance: Decimal::percent(1),
+                }
+                .validate(),
+                Err(ContractError::InvalidQuorumConfig {
+                    reason: "quorum must be at least two".to_string(),
+                })
+            );
+        }
+        assert_eq!(
+            QuorumConfig {
+                quorum: 2,
+                tolerance: Decimal::one(),
+            }
+            .validate(),
+            Err(ContractError::InvalidQuorumConfig {
+                reason: "tolerance must be less than one".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_round_commits_median() {
+        let config = get_quorum_config();
+        let mut round = Round::default();
+
+        assert_eq!(
+            round.submit(&config, submission("feeder1", "1", "coin")),
+            None
+        );
+        assert_eq!(
+            round.status(&Addr::unchecked("feeder1")),
+            RoundStatus::Buffered
+        );
+        // 1 and 1.2 are both too far from their median of 1.1
+        assert_eq!(
+            round.submit(&config, submission("feeder2", "1.2", "coin")),
+            None
+        );
+        assert_eq!(
+            round.submit(&config, submission("feeder3", "1.005", "coin")),
+            Some(dec("1.005"))
+        );
+        assert_eq!(round.committed_rate, Some(dec("1.005")));
+        assert_eq!(
+            round.committed_metadata,
+            Some(submission("feeder3", "1.005", "coin").metadata)
+        );
+        assert_eq!(round.dissenters, vec![Addr::unchecked("feeder2")]);
+        assert_eq!(
+            round.status(&Addr::unchecked("feeder1")),
+            RoundStatus::Committed
+        );
+        assert_eq!(
+            round.status(&Addr::unchecked("feeder2")),
+            RoundStatus::Dissenting
+        );
+        assert!(round.has_submitted(&Addr::unchecked("feeder1")));
+        assert!(!round.has_submitted(&Addr::unchecked("feeder4")));
+
+        // Late submissions don't commit again but can still dissent, on the rate or the denoms
+        assert_eq!(
+            round.submit(&config, submission("feeder4", "1.006", "coin")),
+            None
+        );
+        assert_eq!(
+            round.submit(&config, submission("feeder5", "0.9", "coin")),
+            None
+        );
+        assert_eq!(
+            round.submit(&config, submission("feeder6", "1.005", "othercoin")),
+            None
+        );
+        assert_eq!(round.committed_rate, Some(dec("1.005")));
+        assert_eq!(
+            round.dissenters,
+            vec![
+                Addr::unchecked("feeder2"),
+                Addr::unchecked("feeder5"),
+                Addr::unchecked("feeder6")
+            ]
+        );
+        assert_eq!(round.submissions.len(), 6);
+    }
+
+    #[test]
+    fn test_round_quorum_needs_same_denoms() {
+        let config = get_quorum_config();
+        let mut round = Round::default();
+
+        assert_eq!(
+            round.submit(&config, submission("feeder1", "1", "coin")),
+            None
+        );
+        assert_eq!(
+            round.submit(&config, submission("feeder2", "1", "othercoin")),
+            None
+        );
+        assert_eq!(
+            round.submit(&config, submission("feeder3", "1", "othercoin")),
+            Some(dec("1"))
+        );
+        assert_eq!(
+            round.committed_metadata,
+            Some(submission("feeder3", "1", "othercoin").metadata)
+        );
+        assert_eq!(round.dissenters, vec![Addr::unchecked("feeder1")]);
+    }
+
+    #[test]
+    fn test_rejected_round() {
+        let config = get_quorum_config();
+        let mut round = Round::default();
+
+        round.submit(&config, submission("feeder1", "1", "coin"));
+        assert_eq!(
+            round.submit(&config, submission("feeder2", "1", "coin")),
+            Some(dec("1"))
+        );
+        round.reject();
+        assert!(round.rejected);
+        assert_eq!(
+            round.status(&Addr::unchecked("feeder1")),
+            RoundStatus::Rejected
+        );
+        assert_eq!(round.committed_rate, None);
+        assert_eq!(round.committed_metadata, None);
+
+        // Later submissions are recorded without committing
+        assert_eq!(
+            round.submit(&config, submission("feeder3", "1", "coin")),
+            None
+        );
+        assert_eq!(round.submissions.len(), 3);
+        assert_eq!(round.dissenters, Vec::<Addr>::new());
+    }
+
+    #[test]
+    fn test_prune_rounds() {
+        let mut storage = MockStorage::new();
+        let update_times = |storage: &MockStorage| {
+            ROUNDS
+                .prefix(DENOM)
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap()
+        };
+
+        // Rounds 3 and 5 were opened long ago, but round 3 committed a rate
+        for (update_time, opened_at, committed_rate) in [
+            (1, 10, None),
+            (2, 10, None),
+            (3, 1, Some(Decimal::one())),
+            (4, 10, None),
+            (5, 1, None),
+        ] {
+            let round = Round {
+                opened_at,
+                committed_rate,
+                ..Round::default()
+            };
+            ROUNDS
+                .save(&mut storage, (DENOM, update_time), &round)
+                .unwrap();
+        }
+
+        prune_rounds(&mut storage, DENOM, 2, 5).unwrap();
+        assert_eq!(update_times(&storage), vec![2, 3, 4]);
+
+        clear_rounds(&mut storage, DENOM).unwrap();
+        assert_eq!(update_times(&storage), Vec::<u64>::new());
+    }
+}
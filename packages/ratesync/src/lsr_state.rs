@@ -1,13 +1,20 @@
 use std::collections::VecDeque;
+use std::fmt;
 
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::{Addr, Decimal};
+use cosmwasm_std::{Addr, Decimal, Empty, Order};
 use cw_storage_plus::{Item, Map};
 
+use crate::{
+    lsr_anomaly::{AnomalyDetector, EmaDeviation, MeanDeviation, MedianDeviation, ZScore},
+    lsr_error::ContractError,
+    lsr_helpers::{hash_denom_trace_path, validate_channel_id, validate_port_id},
+};
+
 #[cw_serde]
 pub struct Config {
-    /// Contract owner
+    /// Contract owner, the admin that manages config and grants roles
     pub owner: Addr,
     /// Transfer Channel ID
     pub transfer_channel_i_d: String,
@@ -15,6 +22,156 @@ pub struct Config {
     pub transfer_port_i_d: String,
 }
 
+/// Roles the admin can grant to other addresses
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum Role {
+    /// Posts redemption rates
+    Feeder,
+    /// Takes rates out of service
+    Guardian,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Feeder => "feeder",
+            Role::Guardian => "guardian",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Pause switches, each independently halting one side of the contract
+#[cw_serde]
+#[derive(Default, Copy, Eq)]
+pub struct PauseFlags {
+    /// Refuse new redemption rates
+    pub ingestion: bool,
+    /// Fail the RedemptionRate query
+    pub serving: bool,
+}
+
+impl PauseFlags {
+    /// Combines two scopes, a side is paused if either scope pauses it
+    pub fn or(self, other: PauseFlags) -> PauseFlags {
+        PauseFlags {
+            ingestion: self.ingestion || other.ingestion,
+            serving: self.serving || other.serving,
+        }
+    }
+}
+
+/// Denoms of an stkToken as posted with its rates
+#[cw_serde]
+#[derive(Default)]
+pub struct DenomMetadata {
+    /// stkToken denom as it lives on the controller chain
+    pub stk_denom: String,
+    /// Default bond denom
+    pub default_bond_denom: String,
+}
+
+/// The IBC path an stkToken takes from the controller chain to the oracle chain
+#[cw_serde]
+pub struct TransferPath {
+    /// Transfer Port ID
+    pub transfer_port_i_d: String,
+    /// Transfer Channel ID
+    pub transfer_channel_i_d: String,
+    /// Further hops towards the chain the stkToken originated on, for tokens routed
+    /// through intermediate chains
+    #[serde(default)]
+    pub intermediate_hops: Vec<TransferHop>,
+}
+
+impl TransferPath {
+    pub fn to_denom_trace(&self, base_denom: &str) -> DenomTrace {
+        let mut path = vec![TransferHop {
+            port_id: self.transfer_port_i_d.clone(),
+            channel_id: self.transfer_channel_i_d.clone(),
+        }];
+        path.extend(self.intermediate_hops.iter().cloned());
+
+        DenomTrace {
+            path,
+            base_denom: base_denom.to_string(),
+        }
+    }
+}
+
+/// A single port/channel hop of an IBC denom trace
+#[cw_serde]
+pub struct TransferHop {
+    /// Transfer Port ID
+    pub port_id: String,
+    /// Transfer Channel ID
+    pub channel_id: String,
+}
+
+/// The ICS-20 trace of a denom as it lives on the oracle chain. Hops are ordered from the
+/// oracle chain back to the chain the denom originated on,
+/// e.g. transfer/channel-0/transfer/channel-5/stkuatom
+#[cw_serde]
+pub struct DenomTrace {
+    /// Port/channel hops, starting with the channel on the oracle chain
+    pub path: Vec<TransferHop>,
+    /// Denom on the chain it originated on
+    pub base_denom: String,
+}
+
+impl DenomTrace {
+    /// Validates the base denom and every hop of the trace
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.path.is_empty() {
+            return Err(ContractError::InvalidDenomTrace {
+                reason: "path must contain at least one hop".to_string(),
+            });
+        }
+
+        if self.base_denom.is_empty() {
+            return Err(ContractError::InvalidDenomTrace {
+                reason: "base denom must not be empty".to_string(),
+            });
+        }
+
+        for hop in &self.path {
+            validate_port_id(&hop.port_id)?;
+            validate_channel_id(&hop.channel_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the full trace path, e.g. transfer/channel-0/transfer/channel-5/stkuatom
+    pub fn full_path(&self) -> String {
+        let mut segments: Vec<&str> = vec![];
+        for hop in &self.path {
+            segments.push(&hop.port_id);
+            segments.push(&hop.channel_id);
+        }
+        segments.push(&self.base_denom);
+
+        segments.join("/")
+    }
+
+    /// Returns the IBC denom hash of the full trace path
+    pub fn ibc_hash(&self) -> Result<String, ContractError> {
+        if self.base_denom.starts_with("ibc/") {
+            return Err(ContractError::InvalidRedemptionRateDenom {
+                denom: self.base_denom.clone(),
+            });
+        }
+
+        Ok(hash_denom_trace_path(&self.full_path()))
+    }
+}
+
 /// The RedemptionRate struct represents the c-value of an stkToken
 #[cw_serde]
 pub struct RedemptionRate {
@@ -26,6 +183,94 @@ pub struct RedemptionRate {
     pub update_time: u64,
     /// anomaly detected
     pub anomaly_detected: bool,
+    /// Held back from the RedemptionRate query until approved by the owner
+    #[serde(default)]
+    pub quarantined: bool,
+    /// Set by chain governance through sudo rather than posted by a feeder
+    #[serde(default)]
+    pub overridden: bool,
+}
+
+/// A rate as stored in the history of its denom, whose key holds the denom
+#[cw_serde]
+pub struct RateEntry {
+    /// The c-value of the stkToken
+    pub redemption_rate: Decimal,
+    /// The unix timestamp representing when the c-value was last updated
+    pub update_time: u64,
+    /// anomaly detected
+    pub anomaly_detected: bool,
+    /// Held back from the RedemptionRate query until approved by the owner
+    pub quarantined: bool,
+    /// Set by chain governance through sudo rather than posted by a feeder
+    pub overridden: bool,
+}
+
+impl RateEntry {
+    /// The rate of the denom the entry is stored under
+    pub fn into_rate(self, denom: &str) -> RedemptionRate {
+        RedemptionRate {
+            denom: denom.to_string(),
+            redemption_rate: self.redemption_rate,
+            update_time: self.update_time,
+            anomaly_detected: self.anomaly_detected,
+            quarantined: self.quarantined,
+            overridden: self.overridden,
+        }
+    }
+}
+
+impl From<&RedemptionRate> for RateEntry {
+    fn from(rate: &RedemptionRate) -> Self {
+        RateEntry {
+            redemption_rate: rate.redemption_rate,
+            update_time: rate.update_time,
+            anomaly_detected: rate.anomaly_detected,
+            quarantined: rate.quarantined,
+            overridden: rate.overridden,
+        }
+    }
+}
+
+/// Final state of a denom removed by the admin
+#[cw_serde]
+pub struct DenomTombstone {
+    /// Latest served rate when the denom was removed, None if it had no rates
+    pub final_rate: Option<RedemptionRate>,
+    /// Block time of the removal, in unix seconds
+    pub retired_at: u64,
+}
+
+/// Hook failures of a subscriber contract
+#[cw_serde]
+#[derive(Default)]
+pub struct Subscription {
+    /// Number of hooks the subscriber failed since it subscribed
+    pub failure_count: u64,
+    /// Latest failed hook, None if the subscriber never failed
+    pub last_failure: Option<HookFailure>,
+}
+
+/// A hook that failed in a subscriber contract
+#[cw_serde]
+pub struct HookFailure {
+    /// Update time of the rate the hook was sent for
+    pub update_time: u64,
+    /// Error returned by the subscriber
+    pub error: String,
+    /// Block time of the failure, in unix seconds
+    pub failed_at: u64,
+}
+
+/// A hook sent to a subscriber, used to attribute a failure reply to it
+#[cw_serde]
+pub struct PendingHook {
+    /// stkToken denom as an IBC hash
+    pub denom: String,
+    /// Subscriber contract address
+    pub subscriber: Addr,
+    /// Update time of the rate
+    pub update_time: u64,
 }
 
 impl HasTime for RedemptionRate {
@@ -44,47 +289,142 @@ pub struct History<T: HasTime + Clone> {
     capacity: u64,
 }
 
-const HISTORY_ITEM_CAP: u64 = 100;
+pub const HISTORY_ITEM_CAP: u64 = 100;
+
+/// Capacity and length of the history of a denom, whose rates are in REDEMPTION_RATES
+#[cw_serde]
+pub struct HistoryHead {
+    /// Number of rates kept before the oldest are dropped
+    pub capacity: u64,
+    /// Number of rates currently stored
+    pub len: u64,
+}
 
-impl<T: HasTime + Clone> Default for History<T> {
+impl Default for HistoryHead {
     fn default() -> Self {
-        Self::new(HISTORY_ITEM_CAP)
+        HistoryHead {
+            capacity: HISTORY_ITEM_CAP,
+            len: 0,
+        }
     }
 }
 
 impl<T: HasTime + Clone> History<T> {
-    pub fn new(capacity: u64) -> Self {
-        History {
-            deque: VecDeque::with_capacity(capacity as usize),
-            capacity,
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.deque.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deque.is_empty()
+    }
+
+    /// Returns the items with a time after `start_after` and before `end_before`,
+    /// both bounds being exclusive
+    pub fn range(
+        &self,
+        start_after: Option<u64>,
+        end_before: Option<u64>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = &T> + '_> {
+        let start = start_after.map_or(0, |start_after| {
+            self.deque.partition_point(|m| m.time() <= start_after)
+        });
+        let end = end_before.map_or(self.deque.len(), |end_before| {
+            self.deque.partition_point(|m| m.time() < end_before)
+        });
+        let items = self.deque.range(start..end.max(start));
+
+        match order {
+            Order::Ascending => Box::new(items),
+            Order::Descending => Box::new(items.rev()),
         }
     }
+}
 
-    pub fn add(&mut self, item: T) {
-        match self.deque.binary_search_by_key(&item.time(), |m| m.time()) {
-            Ok(index) => {
-                self.deque.remove(index);
-                self.deque.insert(index, item)
-            }
-            Err(index) => {
-                self.deque.insert(index, item);
-                if self.deque.len() > self.capacity as usize {
-                    self.deque.pop_front();
-                }
-            }
+/// What happens to a redemption rate that fails anomaly detection
+#[cw_serde]
+#[derive(Default, Eq)]
+pub enum AnomalyPolicy {
+    /// Store the rate with `anomaly_detected` set and serve it as usual
+    #[default]
+    AcceptAndFlag,
+    /// Store the rate but don't serve it until the owner approves it
+    Quarantine,
+    /// Refuse the rate
+    Reject,
+}
+
+impl fmt::Display for AnomalyPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnomalyPolicy::AcceptAndFlag => write!(f, "accept_and_flag"),
+            AnomalyPolicy::Quarantine => write!(f, "quarantine"),
+            AnomalyPolicy::Reject => write!(f, "reject"),
         }
     }
+}
 
-    pub fn get_latest(&self) -> Option<T> {
-        self.deque.back().cloned()
+/// How a new redemption rate is compared against the previous rates
+#[cw_serde]
+#[derive(Default, Eq)]
+pub enum AnomalyStrategy {
+    /// Deviation from the arithmetic mean
+    #[default]
+    Mean,
+    /// Deviation from the median
+    Median,
+    /// Deviation from the exponential moving average
+    Ema,
+    /// Number of standard deviations from the mean, the threshold being the maximum z-score
+    ZScore,
+}
+
+impl AnomalyStrategy {
+    /// Returns the detector of the strategy. The z-score strategy ignores the threshold mode.
+    pub fn detector(&self, threshold: Decimal, mode: ThresholdMode) -> Box<dyn AnomalyDetector> {
+        match self {
+            AnomalyStrategy::Mean => Box::new(MeanDeviation { threshold, mode }),
+            AnomalyStrategy::Median => Box::new(MedianDeviation { threshold, mode }),
+            AnomalyStrategy::Ema => Box::new(EmaDeviation { threshold, mode }),
+            AnomalyStrategy::ZScore => Box::new(ZScore {
+                max_z_score: threshold,
+            }),
+        }
     }
+}
 
-    pub fn get_latest_range(&self, n: usize) -> Vec<T> {
-        self.deque.iter().rev().take(n).cloned().collect()
+impl fmt::Display for AnomalyStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnomalyStrategy::Mean => write!(f, "mean"),
+            AnomalyStrategy::Median => write!(f, "median"),
+            AnomalyStrategy::Ema => write!(f, "ema"),
+            AnomalyStrategy::ZScore => write!(f, "z_score"),
+        }
     }
+}
+
+/// How the deviation from the baseline is compared against the threshold
+#[cw_serde]
+#[derive(Default, Copy, Eq)]
+pub enum ThresholdMode {
+    /// The threshold is a fraction of the baseline, e.g. 0.05 allows a 5% deviation
+    #[default]
+    Relative,
+    /// The threshold is in redemption rate units, e.g. 0.05 allows 1.20 against 1.15
+    Absolute,
+}
 
-    pub fn get_all(&self) -> Vec<T> {
-        self.deque.iter().rev().cloned().collect()
+impl fmt::Display for ThresholdMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThresholdMode::Relative => write!(f, "relative"),
+            ThresholdMode::Absolute => write!(f, "absolute"),
+        }
     }
 }
 
@@ -94,6 +434,19 @@ pub struct AnomalyConfig {
     pub count_limit: u64,
     /// Allowed anomaly threshold
     pub threshold: Decimal,
+    /// Whether the threshold is relative to the baseline or absolute.
+    /// Configs stored before 1.1.0 are migrated to absolute.
+    #[serde(default)]
+    pub threshold_mode: ThresholdMode,
+    /// Handling of rates that exceed the threshold
+    #[serde(default)]
+    pub policy: AnomalyPolicy,
+    /// Baseline the new rate is compared against
+    #[serde(default)]
+    pub strategy: AnomalyStrategy,
+    /// Leave rates flagged as anomalous out of the baseline
+    #[serde(default)]
+    pub exclude_flagged: bool,
 }
 
 const ANOMALY_THRESHOLD: Decimal = Decimal::percent(5);
@@ -103,12 +456,69 @@ impl Default for AnomalyConfig {
         AnomalyConfig {
             count_limit: HISTORY_ITEM_CAP,
             threshold: ANOMALY_THRESHOLD,
+            threshold_mode: ThresholdMode::default(),
+            policy: AnomalyPolicy::default(),
+            strategy: AnomalyStrategy::default(),
+            exclude_flagged: false,
         }
     }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const LIQUID_STAKE_RATES: Map<&str, History<RedemptionRate>> = Map::new("liquid_stake_rate");
+/// Addresses granted a role, keyed by the role name and the address
+pub const ROLE_MEMBERS: Map<(&str, &Addr), Empty> = Map::new("role_members");
+
+/// Pause flags applying to every denom
+pub const GLOBAL_PAUSE: Item<PauseFlags> = Item::new("global_pause");
+
+/// Pause flags of individual denoms, keyed by the stkToken ibc hash
+pub const PAUSE_BY_DENOM: Map<&str, PauseFlags> = Map::new("pause_by_denom");
+
+/// IBC connections the admin allows ratesync channels on, keyed by the connection ID
+pub const ALLOWED_IBC_CONNECTIONS: Map<&str, Empty> = Map::new("allowed_ibc_connections");
+
+/// Connection ID of every open ratesync channel, keyed by the channel ID
+pub const IBC_CHANNELS: Map<&str, String> = Map::new("ibc_channels");
+
+/// Histories stored as a single blob per denom before 1.3.0, only read by the migration
+pub const LEGACY_LIQUID_STAKE_RATES: Map<&str, History<RedemptionRate>> =
+    Map::new("liquid_stake_rate");
+
+/// Rates of every stkToken, keyed by the stkToken ibc hash and the update time
+pub const REDEMPTION_RATES: Map<(&str, u64), RateEntry> = Map::new("redemption_rates");
+
+/// History capacity and length of every stkToken, keyed by the stkToken ibc hash
+pub const HISTORY_HEADS: Map<&str, HistoryHead> = Map::new("history_heads");
+
+/// Denoms of every stkToken as of its last posted rate, keyed by the stkToken ibc hash.
+/// Denoms whose rates were all posted before this was recorded have no entry.
+pub const DENOM_METADATA: Map<&str, DenomMetadata> = Map::new("denom_metadata");
+
+/// Ibc hashes of the stkTokens of every bond denom as of their last posted rate, keyed by
+/// (default bond denom, stkToken ibc hash)
+pub const STK_DENOMS_BY_BOND_DENOM: Map<(&str, &str), Empty> = Map::new("stk_denoms_by_bond_denom");
 
 pub const ANOMALY_CONFIG_BY_DENOM: Map<&str, AnomalyConfig> = Map::new("anomaly_config_by_denom");
+
+/// Anomaly config given to denoms the first time a rate is posted for them
+pub const DEFAULT_ANOMALY_CONFIG: Item<AnomalyConfig> = Item::new("default_anomaly_config");
+
+/// Maximum age in seconds of the latest redemption rate before it is considered stale,
+/// keyed by the stkToken ibc hash. Denoms without an entry never go stale.
+pub const MAX_AGE_BY_DENOM: Map<&str, u64> = Map::new("max_age_by_denom");
+
+/// Tombstones of removed denoms, keyed by the stkToken ibc hash. Denoms with a tombstone
+/// refuse new rates and their rate queries fail with a retired error.
+pub const RETIRED_DENOMS: Map<&str, DenomTombstone> = Map::new("retired_denoms");
+
+/// Contracts notified of every new rate of a denom, keyed by the stkToken ibc hash and
+/// the subscriber address
+pub const SUBSCRIPTIONS: Map<(&str, &Addr), Subscription> = Map::new("subscriptions");
+
+/// Hooks sent by the latest ingestion, the reply ID of a hook being its index
+pub const PENDING_HOOKS: Item<Vec<PendingHook>> = Item::new("pending_hooks");
+
+/// Transfer paths registered for individual stkTokens, keyed by the stkToken base denom.
+/// Denoms without a registered path use the path from the config.
+pub const TRANSFER_PATH_BY_DENOM: Map<&str, TransferPath> = Map::new("transfer_path_by_denom");
@@ -0,0 +1,244 @@
+use cosmwasm_std::{StdError, Storage};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Invalid contract name for migration, expected {expected}, got {actual}")]
+    InvalidContractName { expected: String, actual: String },
+
+    #[error("Cannot migrate from version {stored} to older version {current}")]
+    CannotDowngrade { stored: String, current: String },
+
+    #[error("Invalid contract version: {reason}")]
+    InvalidVersion { reason: String },
+}
+
+impl From<semver::Error> for MigrationError {
+    fn from(error: semver::Error) -> Self {
+        MigrationError::InvalidVersion {
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// A state upgrade step, applied when migrating from a version older than `version`
+pub struct MigrationStep<E> {
+    /// The contract version that introduced the state change
+    pub version: &'static str,
+    /// Rewrites the stored state into the layout expected by `version`
+    pub migrate: fn(&mut dyn Storage) -> Result<(), E>,
+}
+
+/// The outcome of a successful migration
+#[derive(Debug, PartialEq)]
+pub struct MigrationInfo {
+    /// The contract version stored before the migration
+    pub from_version: String,
+    /// The contract version stored after the migration
+    pub to_version: String,
+    /// The versions of the state upgrade steps that were applied, in order
+    pub applied_steps: Vec<String>,
+}
+
+/// Checks the stored cw2 contract info against the new contract name and version,
+/// refuses downgrades, runs the pending upgrade steps in order and stores the new version
+pub fn migrate_contract<E: From<MigrationError>>(
+    storage: &mut dyn Storage,
+    contract_name: &str,
+    contract_version: &str,
+    steps: &[MigrationStep<E>],
+) -> Result<MigrationInfo, E> {
+    let stored = get_contract_version(storage).map_err(MigrationError::from)?;
+
+    if stored.contract != contract_name {
+        return Err(MigrationError::InvalidContractName {
+            expected: contract_name.to_string(),
+            actual: stored.contract,
+        }
+        .into());
+    }
+
+    let from = parse_version(&stored.version)?;
+    let to = parse_version(contract_version)?;
+
+    if from > to {
+        return Err(MigrationError::CannotDowngrade {
+            stored: stored.version,
+            current: contract_version.to_string(),
+        }
+        .into());
+    }
+
+    let applied_steps = run_migrations(storage, steps, &from, &to)?;
+
+    set_contract_version(storage, contract_name, contract_version).map_err(MigrationError::from)?;
+
+    Ok(MigrationInfo {
+        from_version: stored.version,
+        to_version: contract_version.to_string(),
+        applied_steps,
+    })
+}
+
+/// Runs every step whose version is newer than `from` and not newer than `to`, in order,
+/// and returns the versions that were applied
+pub fn run_migrations<E: From<MigrationError>>(
+    storage: &mut dyn Storage,
+    steps: &[MigrationStep<E>],
+    from: &Version,
+    to: &Version,
+) -> Result<Vec<String>, E> {
+    let mut applied = vec![];
+
+    for step in steps {
+        let step_version = parse_version(step.version)?;
+        if step_version > *from && step_version <= *to {
+            (step.migrate)(storage)?;
+            applied.push(step.version.to_string());
+        }
+    }
+
+    Ok(applied)
+}
+
+fn parse_version(version: &str) -> Result<Version, MigrationError> {
+    Ok(version.parse::<Version>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{StdError, Storage};
+    use cw2::{get_contract_version, set_contract_version};
+
+    use super::{migrate_contract, MigrationError, MigrationInfo, MigrationStep};
+
+    const CONTRACT_NAME: &str = "crates.io:test-contract";
+
+    fn step_one(storage: &mut dyn Storage) -> Result<(), MigrationError> {
+        storage.set(b"steps", b"1");
+        Ok(())
+    }
+
+    fn step_two(storage: &mut dyn Storage) -> Result<(), MigrationError> {
+        let mut steps = storage.get(b"steps").unwrap_or_default();
+        steps.extend_from_slice(b"2");
+        storage.set(b"steps", &steps);
+        Ok(())
+    }
+
+    fn failing_step(_storage: &mut dyn Storage) -> Result<(), MigrationError> {
+        Err(MigrationError::Std(StdError::generic_err("failed")))
+    }
+
+    const TEST_STEPS: &[MigrationStep<MigrationError>] = &[
+        MigrationStep {
+            version: "1.1.0",
+            migrate: step_one,
+        },
+        MigrationStep {
+            version: "1.2.0",
+            migrate: step_two,
+        },
+    ];
+
+    #[test]
+    fn test_runs_all_steps_in_order() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "1.0.0").unwrap();
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.2.0", TEST_STEPS).unwrap();
+        assert_eq!(
+            res,
+            MigrationInfo {
+                from_version: "1.0.0".to_string(),
+                to_version: "1.2.0".to_string(),
+                applied_steps: vec!["1.1.0".to_string(), "1.2.0".to_string()],
+            }
+        );
+        assert_eq!(storage.get(b"steps"), Some(b"12".to_vec()));
+        assert_eq!(get_contract_version(&storage).unwrap().version, "1.2.0");
+    }
+
+    #[test]
+    fn test_skips_applied_and_future_steps() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "1.1.0").unwrap();
+
+        // Only steps in (1.1.0, 1.1.5] should run
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.1.5", TEST_STEPS).unwrap();
+        assert!(res.applied_steps.is_empty());
+        assert_eq!(storage.get(b"steps"), None);
+        assert_eq!(get_contract_version(&storage).unwrap().version, "1.1.5");
+    }
+
+    #[test]
+    fn test_same_version() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "1.2.0").unwrap();
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.2.0", TEST_STEPS).unwrap();
+        assert!(res.applied_steps.is_empty());
+    }
+
+    #[test]
+    fn test_refuses_downgrade() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "1.2.0").unwrap();
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.1.0", TEST_STEPS);
+        assert_eq!(
+            res,
+            Err(MigrationError::CannotDowngrade {
+                stored: "1.2.0".to_string(),
+                current: "1.1.0".to_string(),
+            })
+        );
+        assert_eq!(storage.get(b"steps"), None);
+    }
+
+    #[test]
+    fn test_refuses_other_contract() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, "crates.io:other-contract", "1.0.0").unwrap();
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.2.0", TEST_STEPS);
+        assert_eq!(
+            res,
+            Err(MigrationError::InvalidContractName {
+                expected: CONTRACT_NAME.to_string(),
+                actual: "crates.io:other-contract".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_stored_version() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "v1").unwrap();
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.2.0", TEST_STEPS);
+        assert!(matches!(res, Err(MigrationError::InvalidVersion { .. })));
+    }
+
+    #[test]
+    fn test_failing_step() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, CONTRACT_NAME, "1.0.0").unwrap();
+        let steps = &[MigrationStep {
+            version: "1.1.0",
+            migrate: failing_step,
+        }];
+
+        let res = migrate_contract(&mut storage, CONTRACT_NAME, "1.1.0", steps);
+        assert_eq!(
+            res,
+            Err(MigrationError::Std(StdError::generic_err("failed")))
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, BlockInfo, StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Only the owner can propose or cancel an ownership transfer")]
+    NotOwner {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwnership {},
+
+    #[error("Only the proposed owner can accept the ownership transfer")]
+    NotPendingOwner {},
+
+    #[error("The ownership transfer expired at {expires_at}")]
+    OwnershipTransferExpired { expires_at: u64 },
+
+    #[error("Invalid ownership transfer expiry {expires_at}, it must be after {now}")]
+    InvalidExpiry { expires_at: u64, now: u64 },
+}
+
+/// An ownership transfer waiting to be accepted by the proposed owner
+#[cw_serde]
+pub struct PendingOwnership {
+    /// The address proposed as the new owner
+    pub new_owner: Addr,
+    /// Unix timestamp in seconds after which the proposal can no longer be accepted
+    pub expires_at: Option<u64>,
+}
+
+impl PendingOwnership {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| block.time.seconds() >= expires_at)
+    }
+}
+
+#[cw_serde]
+pub struct PendingOwnershipResponse {
+    /// The pending ownership transfer, if any
+    pub pending_ownership: Option<PendingOwnership>,
+}
+
+pub const PENDING_OWNERSHIP: Item<PendingOwnership> = Item::new("pending_ownership");
+
+/// Stores a proposal to transfer ownership to `new_owner`, replacing any previous proposal
+pub fn propose_ownership(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    owner: &Addr,
+    sender: &Addr,
+    new_owner: &str,
+    expires_at: Option<u64>,
+) -> Result<PendingOwnership, OwnershipError> {
+    if sender != owner {
+        return Err(OwnershipError::NotOwner {});
+    }
+
+    if let Some(expires_at) = expires_at {
+        let now = block.time.seconds();
+        if expires_at <= now {
+            return Err(OwnershipError::InvalidExpiry { expires_at, now });
+        }
+    }
+
+    let pending_ownership = PendingOwnership {
+        new_owner: api.addr_validate(new_owner)?,
+        expires_at,
+    };
+    PENDING_OWNERSHIP.save(storage, &pending_ownership)?;
+
+    Ok(pending_ownership)
+}
+
+/// Completes the pending transfer and returns the new owner, which the caller stores
+pub fn accept_ownership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+) -> Result<Addr, OwnershipError> {
+    let pending_ownership = PENDING_OWNERSHIP
+        .may_load(storage)?
+        .ok_or(OwnershipError::NoPendingOwnership {})?;
+
+    if *sender != pending_ownership.new_owner {
+        return Err(OwnershipError::NotPendingOwner {});
+    }
+
+    if pending_ownership.is_expired(block) {
+        return Err(OwnershipError::OwnershipTransferExpired {
+            expires_at: pending_ownership.expires_at.unwrap_or_default(),
+        });
+    }
+
+    PENDING_OWNERSHIP.remove(storage);
+
+    Ok(pending_ownership.new_owner)
+}
+
+/// Withdraws the pending transfer
+pub fn cancel_ownership(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    sender: &Addr,
+) -> Result<(), OwnershipError> {
+    if sender != owner {
+        return Err(OwnershipError::NotOwner {});
+    }
+
+    if !PENDING_OWNERSHIP.exists(storage) {
+        return Err(OwnershipError::NoPendingOwnership {});
+    }
+    PENDING_OWNERSHIP.remove(storage);
+
+    Ok(())
+}
+
+pub fn query_pending_ownership(storage: &dyn Storage) -> StdResult<PendingOwnershipResponse> {
+    Ok(PendingOwnershipResponse {
+        pending_ownership: PENDING_OWNERSHIP.may_load(storage)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::Addr;
+
+    use super::{
+        accept_ownership, cancel_ownership, propose_ownership, query_pending_ownership,
+        OwnershipError, PendingOwnership,
+    };
+
+    #[test]
+    fn test_transfer_ownership() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("new_owner");
+
+        let res = propose_ownership(
+            &mut deps.storage,
+            &deps.api,
+            &env.block,
+            &owner,
+            &new_owner,
+            "new_owner",
+            None,
+        );
+        assert_eq!(res, Err(OwnershipError::NotOwner {}));
+
+        propose_ownership(
+            &mut deps.storage,
+            &deps.api,
+            &env.block,
+            &owner,
+            &owner,
+            "new_owner",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            query_pending_ownership(deps.as_ref().storage)
+                .unwrap()
+                .pending_ownership,
+            Some(PendingOwnership {
+                new_owner: new_owner.clone(),
+                expires_at: None,
+            })
+        );
+
+        let res = accept_ownership(deps.as_mut().storage, &env.block, &owner);
+        assert_eq!(res, Err(OwnershipError::NotPendingOwner {}));
+
+        let res = accept_ownership(deps.as_mut().storage, &env.block, &new_owner);
+        assert_eq!(res, Ok(new_owner.clone()));
+
+        // The proposal can only be accepted once
+        let res = accept_ownership(deps.as_mut().storage, &env.block, &new_owner);
+        assert_eq!(res, Err(OwnershipError::NoPendingOwnership {}));
+    }
+
+    #[test]
+    fn test_expired_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("new_owner");
+        let now = env.block.time.seconds();
+
+        let res = propose_ownership(
+            &mut deps.storage,
+            &deps.api,
+            &env.block,
+            &owner,
+            &owner,
+            "new_owner",
+            Some(now),
+        );
+        assert_eq!(
+            res,
+            Err(OwnershipError::InvalidExpiry {
+                expires_at: now,
+                now
+            })
+        );
+
+        propose_ownership(
+            &mut deps.storage,
+            &deps.api,
+            &env.block,
+            &owner,
+            &owner,
+            "new_owner",
+            Some(now + 100),
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = accept_ownership(deps.as_mut().storage, &env.block, &new_owner);
+        assert_eq!(
+            res,
+            Err(OwnershipError::OwnershipTransferExpired {
+                expires_at: now + 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_cancel_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("new_owner");
+
+        let res = cancel_ownership(deps.as_mut().storage, &owner, &owner);
+        assert_eq!(res, Err(OwnershipError::NoPendingOwnership {}));
+
+        propose_ownership(
+            &mut deps.storage,
+            &deps.api,
+            &env.block,
+            &owner,
+            &owner,
+            "new_owner",
+            None,
+        )
+        .unwrap();
+
+        let res = cancel_ownership(deps.as_mut().storage, &owner, &new_owner);
+        assert_eq!(res, Err(OwnershipError::NotOwner {}));
+
+        cancel_ownership(deps.as_mut().storage, &owner, &owner).unwrap();
+        assert_eq!(
+            query_pending_ownership(deps.as_ref().storage)
+                .unwrap()
+                .pending_ownership,
+            None
+        );
+
+        let res = accept_ownership(deps.as_mut().storage, &env.block, &new_owner);
+        assert_eq!(res, Err(OwnershipError::NoPendingOwnership {}));
+    }
+}